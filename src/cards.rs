@@ -0,0 +1,278 @@
+//! Two-sided character cards.
+//!
+//! A printed card carries two characters, one per side, in a shared colour, and each player picks
+//! a side.  A [`Pairing`] says which characters share a card: none at all, pairs fixed by the
+//! designers, or pairs the scoring picks itself for each roster.  Each pair adds a term to the
+//! fitness that grows with the difference in overall strength between the two sides and with how
+//! alike their starting values are, so that picking a side is a real choice but never a bad one.
+//! With an odd roster one character gets a card of its own.
+
+use crate::individual::Individual;
+use crate::rankings::Rankings;
+use crate::rules::TRAITS;
+
+/// Pairings accepted by [`parse_pairing`].
+pub const PAIRINGS: &str = "none, auto, NAME/NAME[,NAME/NAME...]";
+
+/// Card colours, handed out in order.
+pub const COLOURS: [&str; 8] = [
+    "Red", "Blue", "Green", "Yellow", "Purple", "Orange", "White", "Black",
+];
+
+/// Two sides whose starting values differ by less than this in total count as too alike.
+pub const DISTINCT: i32 = 4;
+
+// Largest roster paired by trying every matching; bigger ones pair neighbours in strength
+const MATCHING_LIMIT: usize = 12;
+
+/// Which characters share a card.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Pairing {
+    /// Every character has a card to itself and pairs play no part in the fitness
+    #[default]
+    None,
+    /// Whichever pairs score best for each roster
+    Auto,
+    /// Pairs fixed by the designers; characters left out get a card to themselves
+    Fixed(Vec<(String, String)>),
+}
+
+impl Pairing {
+    /// Configuration text that [`parse_pairing`] turns back into this pairing.
+    pub fn describe(&self) -> String {
+        match self {
+            Pairing::None => "none".to_string(),
+            Pairing::Auto => "auto".to_string(),
+            Pairing::Fixed(pairs) => pairs
+                .iter()
+                .map(|(a, b)| format!("{}/{}", a, b))
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+
+    /// The cards of a roster: each pair, then any character on a card of its own, in name order.
+    pub fn cards(&self, ind: &Individual) -> Vec<(String, Option<String>)> {
+        let pairs = match self {
+            Pairing::None => Vec::new(),
+            Pairing::Auto => best_pairs(ind).1,
+            Pairing::Fixed(pairs) => pairs.clone(),
+        };
+
+        let mut cards: Vec<(String, Option<String>)> = pairs
+            .iter()
+            .map(|(a, b)| (a.clone(), Some(b.clone())))
+            .collect();
+        for name in ind.names() {
+            if !pairs.iter().any(|(a, b)| *a == name || *b == name) {
+                cards.push((name, None));
+            }
+        }
+        cards
+    }
+
+    /// The pair term of the fitness: the summed [`pair_cost`] of every card with two sides.
+    pub fn score(&self, ind: &Individual) -> i32 {
+        match self {
+            Pairing::None => 0,
+            Pairing::Auto => best_pairs(ind).0,
+            Pairing::Fixed(pairs) => pairs.iter().map(|(a, b)| pair_cost(ind, a, b)).sum(),
+        }
+    }
+}
+
+/// Build a pairing from configuration text such as `auto` or `Herman/John,Liam/Kyle`.  Every
+/// name must be one of `names` and appear at most once.
+pub fn parse_pairing(text: &str, names: &[String]) -> Result<Pairing, String> {
+    match text {
+        "none" => return Ok(Pairing::None),
+        "auto" => return Ok(Pairing::Auto),
+        _ => (),
+    }
+
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for pair in text.split(',') {
+        let (a, b) = pair
+            .split_once('/')
+            .map(|(a, b)| (a.trim(), b.trim()))
+            .ok_or(format!("Expected NAME/NAME, found {}", pair))?;
+        if a == b {
+            return Err(format!("{} cannot share a card with itself", a));
+        }
+        for name in [a, b] {
+            if !names.iter().any(|x| x == name) {
+                return Err(format!("{} is not in the rankings", name));
+            }
+            if pairs.iter().any(|(a, b)| a == name || b == name) {
+                return Err(format!("{} is on more than one card", name));
+            }
+        }
+        pairs.push((a.to_string(), b.to_string()));
+    }
+    Ok(Pairing::Fixed(pairs))
+}
+
+/// Overall strength of a character: the strengths of its four traits added up.
+pub fn power(ind: &Individual, name: &str) -> f32 {
+    TRAITS.iter().map(|attr| ind.attr_score(name, attr)).sum()
+}
+
+/// What putting two characters on one card costs: their difference in [`power`], rounded down,
+/// plus how far the total difference of their starting values falls short of [`DISTINCT`].
+pub fn pair_cost(ind: &Individual, a: &str, b: &str) -> i32 {
+    let balance = (power(ind, a) - power(ind, b)).abs() as i32;
+    let difference: i32 = TRAITS
+        .iter()
+        .map(|attr| {
+            let (x, y) = (ind.track(a, attr).unwrap(), ind.track(b, attr).unwrap());
+            (x.0[x.1] - y.0[y.1]).abs()
+        })
+        .sum();
+    balance + (DISTINCT - difference).max(0)
+}
+
+// The cheapest way to pair up a roster, leaving one character alone when the roster is odd
+fn best_pairs(ind: &Individual) -> (i32, Vec<(String, String)>) {
+    let names = ind.names();
+    let n = names.len();
+    if n > MATCHING_LIMIT {
+        let mut order = names.clone();
+        order.sort_by(|a, b| power(ind, a).partial_cmp(&power(ind, b)).unwrap());
+        let pairs: Vec<(String, String)> = order
+            .chunks_exact(2)
+            .map(|x| (x[0].clone(), x[1].clone()))
+            .collect();
+        let cost = pairs.iter().map(|(a, b)| pair_cost(ind, a, b)).sum();
+        return (cost, pairs);
+    }
+
+    let costs: Vec<Vec<i32>> = names
+        .iter()
+        .map(|a| names.iter().map(|b| pair_cost(ind, a, b)).collect())
+        .collect();
+
+    // Cheapest cost of pairing everyone outside `mask`, taking the lowest unpaired character
+    // first; `alone` says whether the odd one out is still to be chosen
+    let full = (1usize << n) - 1;
+    let mut memo = vec![[None; 2]; 1 << n];
+    fn solve(
+        mask: usize,
+        alone: usize,
+        full: usize,
+        costs: &[Vec<i32>],
+        memo: &mut [[Option<(i32, usize)>; 2]],
+    ) -> i32 {
+        if mask == full {
+            return 0;
+        }
+        if let Some((cost, _)) = memo[mask][alone] {
+            return cost;
+        }
+        let i = (!mask).trailing_zeros() as usize;
+        let mut best = (i32::MAX, i);
+        if alone == 1 {
+            let cost = solve(mask | 1 << i, 0, full, costs, memo);
+            best = (cost, i);
+        }
+        for j in (i + 1..costs.len()).filter(|j| mask & 1 << j == 0) {
+            let rest = solve(mask | 1 << i | 1 << j, alone, full, costs, memo);
+            if rest != i32::MAX && costs[i][j] + rest < best.0 {
+                best = (costs[i][j] + rest, j);
+            }
+        }
+        memo[mask][alone] = Some(best);
+        best.0
+    }
+    let cost = solve(0, n % 2, full, &costs, &mut memo);
+
+    // Walk the choices back from the empty mask
+    let mut pairs = Vec::new();
+    let (mut mask, mut alone) = (0, n % 2);
+    while mask != full {
+        let i = (!mask).trailing_zeros() as usize;
+        let (_, j) = memo[mask][alone].unwrap();
+        if j == i {
+            mask |= 1 << i;
+            alone = 0;
+        } else {
+            pairs.push((names[i].clone(), names[j].clone()));
+            mask |= 1 << i | 1 << j;
+        }
+    }
+    (cost, pairs)
+}
+
+/// Every card of a roster with both sides next to each other, each side headed by its name and
+/// followed by its flavour.
+pub fn render(ind: &Individual, rankings: &Rankings) -> String {
+    let mut text = String::new();
+    for (i, (front, back)) in rankings.pairing.cards(ind).iter().enumerate() {
+        let colour = COLOURS[i % COLOURS.len()];
+        let mut sides: Vec<Vec<String>> = [Some(front), back.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|name| {
+                let mut lines = vec![format!("{}:", name)];
+                lines.extend(ind.sheet(name).lines().map(|x| x.to_string()));
+                lines.push(format!("Power: {:.1}", power(ind, name)));
+                lines.extend(rankings.flavour.lines(name));
+                lines
+            })
+            .collect();
+
+        match back {
+            Some(back) => text.push_str(&format!(
+                "# Card {}, {}: {} / {}, cost {}\n",
+                i + 1,
+                colour,
+                front,
+                back,
+                pair_cost(ind, front, back)
+            )),
+            None => text.push_str(&format!("# Card {}, {}: {}\n", i + 1, colour, front)),
+        }
+        let rows = sides.iter().map(|x| x.len()).max().unwrap();
+        for side in sides.iter_mut() {
+            side.resize(rows, String::new());
+        }
+        let width = sides[0].iter().map(|x| x.len()).max().unwrap();
+        for (row, line) in sides[0].iter().enumerate() {
+            match sides.get(1) {
+                Some(other) => text.push_str(&format!(
+                    "{:<width$}  |  {}\n",
+                    line,
+                    other[row],
+                    width = width
+                )),
+                None => text.push_str(&format!("{}\n", line)),
+            }
+        }
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> Vec<String> {
+        ["Anna", "Bo", "Cy"].map(|x| x.to_string()).to_vec()
+    }
+
+    #[test]
+    fn pairs_are_trimmed() {
+        assert_eq!(
+            parse_pairing(" Anna / Bo ", &names()),
+            Ok(Pairing::Fixed(vec![("Anna".to_string(), "Bo".to_string())]))
+        );
+    }
+
+    #[test]
+    fn bad_pairs_say_why() {
+        let error = |text| parse_pairing(text, &names()).err().unwrap();
+        assert!(error("Anna/ Anna").contains("with itself"));
+        assert!(error("Anna/Bo,Cy/ Anna").contains("more than one card"));
+        assert!(error("Anna/Dee").contains("not in the rankings"));
+    }
+}
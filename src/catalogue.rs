@@ -0,0 +1,271 @@
+//! Every legal track and character, listed outright instead of reached by mutation.
+//!
+//! A legal track is eight values from 1 to 8 that never fall, never climb by more than 2, start
+//! at 4 or below and end at 5 or above: a few thousand of them.  Pairing each with a starting
+//! index gives the choices for one trait, and four of those that fit together under
+//! [`rules::valid_indexes`] and the track total make a character block.  There are far too many
+//! blocks to list, so [`Catalogue::count_blocks`] counts them without building them.
+
+use crate::locks::Locks;
+use crate::rules::{self, Stats, Track, MAX_TOTAL, MIN_TOTAL, STARTING_SUM, TRAITS};
+use std::collections::{BTreeMap, HashMap};
+
+// (starting value, starting index, track total) -> number of choices
+type Profile = HashMap<(i32, usize, i32), u64>;
+// (starting value, starting index, track total) -> the first choices with it
+type Choices<'a> = BTreeMap<(i32, usize, i32), Vec<&'a Track>>;
+
+/// The legal choices for each trait of one character.
+#[derive(Debug, Clone)]
+pub struct Catalogue {
+    /// Legal tracks with starting indexes, in [`TRAITS`] order
+    starts: Vec<Vec<Track>>,
+}
+
+impl Catalogue {
+    /// Every legal choice for every trait.
+    pub fn new() -> Catalogue {
+        let tracks = tracks();
+        Catalogue {
+            starts: TRAITS.iter().map(|attr| starts(&tracks, attr)).collect(),
+        }
+    }
+
+    /// The choices left for one character once its locked traits are pinned.
+    pub fn for_character(name: &str, locks: &Locks) -> Catalogue {
+        let locked: Stats = TRAITS
+            .iter()
+            .filter_map(|attr| Some((attr.to_string(), locks.locked(name, attr)?.clone())))
+            .collect();
+        Catalogue::pinned(&locked)
+    }
+
+    /// The choices left for a character with the given traits pinned.
+    pub fn pinned(traits: &Stats) -> Catalogue {
+        let mut catalogue = Catalogue::new();
+        for (i, attr) in TRAITS.iter().enumerate() {
+            if let Some(pinned) = traits.get(*attr) {
+                catalogue.starts[i] = vec![pinned.clone()];
+            }
+        }
+        catalogue
+    }
+
+    /// Legal tracks with starting indexes for one trait.
+    pub fn starts(&self, attr: &str) -> &[Track] {
+        let i = TRAITS.iter().position(|x| *x == attr).unwrap();
+        &self.starts[i]
+    }
+
+    /// How many legal character blocks there are, split into constitution 10 and 11.
+    pub fn count_blocks(&self) -> (u64, u64) {
+        let profiles: Vec<Profile> = self.starts.iter().map(|x| profile(x)).collect();
+        let front = combine(&profiles[0], &profiles[1]);
+        let back = combine(&profiles[2], &profiles[3]);
+
+        let mut counts = (0, 0);
+        for ((value_a, index_a, total_a), count_a) in front.iter() {
+            for ((value_b, index_b, total_b), count_b) in back.iter() {
+                let total = total_a + total_b;
+                if value_a + value_b != STARTING_SUM || !(MIN_TOTAL..=MAX_TOTAL).contains(&total) {
+                    continue;
+                }
+                match index_a + index_b {
+                    10 => counts.0 += count_a * count_b,
+                    11 => counts.1 += count_a * count_b,
+                    _ => (),
+                }
+            }
+        }
+        counts
+    }
+
+    /// Every legal character block, lazily.  Check [`Catalogue::count_blocks`] first; without
+    /// locks this never finishes.
+    pub fn blocks(&self) -> impl Iterator<Item = Stats> + '_ {
+        let [might, speed, know, sanity] = [0, 1, 2, 3].map(|i| &self.starts[i]);
+        might.iter().flat_map(move |m| {
+            speed.iter().flat_map(move |s| {
+                know.iter().flat_map(move |k| {
+                    sanity.iter().filter_map(move |x| {
+                        let stats: Stats = TRAITS
+                            .iter()
+                            .zip([m, s, k, x])
+                            .map(|(attr, tup)| (attr.to_string(), tup.clone()))
+                            .collect();
+                        let total = rules::total(&stats);
+                        if rules::valid_indexes(&stats) && (MIN_TOTAL..=MAX_TOTAL).contains(&total)
+                        {
+                            Some(stats)
+                        } else {
+                            None
+                        }
+                    })
+                })
+            })
+        })
+    }
+
+    /// One legal character block, or None when there is none.  Unlike [`Catalogue::blocks`] this
+    /// answers quickly either way, by matching traits up through their starting values, indexes
+    /// and totals.
+    pub fn first_block(&self) -> Option<Stats> {
+        let [might, speed, know, sanity] = [0, 1, 2, 3].map(|i| choices(&self.starts[i]));
+        let front = pair(&might, &speed);
+        let back = pair(&know, &sanity);
+        for ((value, index, total), first) in front.iter() {
+            for constitution in 10..=11usize {
+                let Some(index) = constitution.checked_sub(*index) else {
+                    continue;
+                };
+                let low = (STARTING_SUM - value, index, MIN_TOTAL - total);
+                let high = (STARTING_SUM - value, index, MAX_TOTAL - total);
+                if let Some((_, last)) = back.range(low..=high).next() {
+                    let stats = TRAITS
+                        .iter()
+                        .zip(first.iter().chain(last.iter()))
+                        .map(|(attr, tup)| (attr.to_string(), (*tup).clone()))
+                        .collect();
+                    return Some(stats);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Default for Catalogue {
+    fn default() -> Catalogue {
+        Catalogue::new()
+    }
+}
+
+/// Every legal eight-value track, in increasing order.
+pub fn tracks() -> Vec<Vec<i32>> {
+    let mut tracks = Vec::new();
+    let mut track = Vec::with_capacity(8);
+    for first in 1..=4 {
+        track.push(first);
+        extend(&mut track, &mut tracks);
+        track.pop();
+    }
+    tracks
+}
+
+// Grow a partial track by every legal step, keeping the complete legal ones
+fn extend(track: &mut Vec<i32>, tracks: &mut Vec<Vec<i32>>) {
+    if track.len() == 8 {
+        if rules::valid_track(track) {
+            tracks.push(track.clone());
+        }
+        return;
+    }
+    let last = *track.last().unwrap();
+    for value in last..=(last + 2).min(8) {
+        track.push(value);
+        extend(track, tracks);
+        track.pop();
+    }
+}
+
+// Every track paired with every starting index it can legally use for a trait
+fn starts(tracks: &[Vec<i32>], attr: &str) -> Vec<Track> {
+    let mut starts = Vec::new();
+    for track in tracks.iter() {
+        for index in 2..=4 {
+            if track[index] >= rules::minimum_start(attr) {
+                starts.push((track.clone(), index));
+            }
+        }
+    }
+    starts
+}
+
+// One choice for each starting value, index and total
+fn choices(starts: &[Track]) -> Choices<'_> {
+    let mut choices = BTreeMap::new();
+    for tup in starts.iter() {
+        let key = (tup.0[tup.1], tup.1, tup.0.iter().sum());
+        choices.entry(key).or_insert_with(|| vec![tup]);
+    }
+    choices
+}
+
+// Two traits side by side, one pair for each summed starting value, index and total
+fn pair<'a>(a: &Choices<'a>, b: &Choices<'a>) -> Choices<'a> {
+    let mut choices = BTreeMap::new();
+    for ((value_a, index_a, total_a), first) in a.iter() {
+        for ((value_b, index_b, total_b), last) in b.iter() {
+            let key = (value_a + value_b, index_a + index_b, total_a + total_b);
+            choices
+                .entry(key)
+                .or_insert_with(|| first.iter().chain(last.iter()).copied().collect());
+        }
+    }
+    choices
+}
+
+fn profile(starts: &[Track]) -> Profile {
+    let mut profile = HashMap::new();
+    for tup in starts.iter() {
+        let key = (tup.0[tup.1], tup.1, tup.0.iter().sum());
+        *profile.entry(key).or_insert(0) += 1;
+    }
+    profile
+}
+
+fn combine(a: &Profile, b: &Profile) -> Profile {
+    let mut profile = HashMap::new();
+    for ((value_a, index_a, total_a), count_a) in a.iter() {
+        for ((value_b, index_b, total_b), count_b) in b.iter() {
+            let key = (value_a + value_b, index_a + index_b, total_a + total_b);
+            *profile.entry(key).or_insert(0) += count_a * count_b;
+        }
+    }
+    profile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sheets;
+
+    fn pinned(text: &str) -> Catalogue {
+        let traits = sheets::parse_rosters(text).unwrap().remove(0).remove(0).1;
+        Catalogue::pinned(&traits)
+    }
+
+    #[test]
+    fn every_track_is_legal_and_distinct() {
+        let tracks = tracks();
+        assert!(tracks.iter().all(|x| rules::valid_track(x)));
+        let mut sorted = tracks.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), tracks.len());
+    }
+
+    #[test]
+    fn counts_match_the_blocks_listed() {
+        let catalogue = pinned(
+            "Kyle:\nMight: 2 3 [4] 5 5 6 7 8\nSpeed: 2 3 3 [4] 5 6 7 8\nKnow: 2 2 3 [3] 4 5 6 7\n",
+        );
+        let (tens, elevens) = catalogue.count_blocks();
+        let blocks: Vec<Stats> = catalogue.blocks().collect();
+        assert!(tens + elevens > 0);
+        assert_eq!(blocks.len() as u64, tens + elevens);
+        let listed_tens = blocks
+            .iter()
+            .filter(|x| rules::constitution(x) == 10)
+            .count();
+        assert_eq!(listed_tens as u64, tens);
+        assert!(catalogue.first_block().is_some());
+    }
+
+    #[test]
+    fn impossible_pins_have_no_blocks() {
+        let catalogue = pinned("Kyle:\nMight: 4 5 6 7 [8] 8 8 8\nSpeed: 4 5 6 7 [8] 8 8 8\n");
+        assert_eq!(catalogue.count_blocks(), (0, 0));
+        assert!(catalogue.first_block().is_none());
+    }
+}
@@ -0,0 +1,391 @@
+//! Saving and loading whole populations.
+//!
+//! Saved populations are plain text:
+//!
+//! ```text
+//! generation 1200
+//! counter 3400
+//! seed 9876543210
+//! elapsed 12.345
+//! grid 1000 1000 100
+//! selection tournament:4
+//! replacement worst
+//! solver ga
+//! hall_of_fame 10
+//! metric footrule
+//! targets 3.25,4,4,4
+//! pairs none
+//!
+//! individual 17 453 812
+//! Anthony Might=2,2,3,4,5,6,7,8@2 Speed=2,2,3,4,5,6,7,8@3 Know=... Sanity=...
+//! Herman Might=...
+//!
+//! famous 9 120 44
+//! Anthony Might=...
+//! ```
+//!
+//! `famous` blocks are the hall of fame, which may hold individuals no longer on the grid.
+
+use crate::cards;
+use crate::ga::{self, CellularGa};
+use crate::hall_of_fame::HallOfFame;
+use crate::individual::Individual;
+use crate::locks::Locks;
+use crate::metric;
+use crate::population::{Betrayal, Population};
+use crate::rankings::Rankings;
+use crate::rules::{Targets, Track, TRAITS};
+use crate::trajectory;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::time::Duration;
+
+/// Where interrupted runs, autosaves and batch runs write their population.
+pub const CHECKPOINT_FILE: &str = "checkpoint.txt";
+
+/// Write a population to a file.  Saving reseeds the population's generator from itself and
+/// records that seed, so a run resumed from the file draws exactly the numbers the original run
+/// would have drawn next.
+pub fn save(pop: &mut Population, file: &str) -> io::Result<()> {
+    let seed: u64 = pop.rng.gen();
+    pop.rng = StdRng::seed_from_u64(seed);
+
+    let mut text = format!(
+        "generation {}\ncounter {}\nseed {}\nelapsed {:.3}\ngrid {} {} {}\nselection {}\nreplacement {}\nsolver {}\nhall_of_fame {}\nmetric {}\ntargets {}\npairs {}\n",
+        pop.generation,
+        pop.grid.counter,
+        seed,
+        pop.elapsed.as_secs_f64(),
+        pop.grid.x_axis,
+        pop.grid.y_axis,
+        pop.grid.window,
+        pop.selection.describe(),
+        pop.replacement.describe(),
+        pop.solver.describe(),
+        pop.hall_of_fame.capacity,
+        pop.betrayal.rankings.metric.describe(),
+        pop.betrayal.rankings.targets.describe(),
+        pop.betrayal.rankings.pairing.describe()
+    );
+    for ind in pop.grid.members.iter() {
+        write_individual(&mut text, "individual", ind);
+    }
+    for ind in pop.hall_of_fame.entries() {
+        write_individual(&mut text, "famous", ind);
+    }
+
+    // Write beside the target first so an interrupted save never clobbers the last good file
+    let tmp = format!("{}.tmp", file);
+    fs::write(&tmp, text)?;
+    fs::rename(&tmp, file)
+}
+
+/// Read a saved population.  Every individual must rank the same characters as `rankings`,
+/// follow the rules and match `locks`.
+pub fn load(file: &str, rankings: &Rankings, locks: &Locks) -> Result<Population, String> {
+    let buffer = fs::read_to_string(file).map_err(|e| e.to_string())?;
+    let mut chunks = buffer.split("\n\n").filter(|x| !x.trim().is_empty());
+
+    let mut header = HashMap::new();
+    for line in chunks.next().ok_or("empty file")?.lines() {
+        let (key, value) = line.split_once(' ').ok_or(format!("bad line: {}", line))?;
+        header.insert(key, value);
+    }
+    let field = |key: &str| header.get(key).copied().ok_or(format!("missing {}", key));
+    let number = |key: &str| -> Result<u64, String> {
+        field(key)?
+            .parse()
+            .map_err(|_| format!("bad {}: {}", key, field(key).unwrap()))
+    };
+    let grid: Vec<i32> = field("grid")?
+        .split_whitespace()
+        .map(|x| x.parse().map_err(|_| format!("bad grid: {}", x)))
+        .collect::<Result<_, _>>()?;
+    let [x_axis, y_axis, window] = grid[..] else {
+        return Err("grid needs x axis, y axis and window".to_string());
+    };
+    // Neighbourhoods reach half a window past a point, which must not overflow
+    let axes = 1..=i32::MAX / 2;
+    if !axes.contains(&x_axis) || !axes.contains(&y_axis) {
+        return Err(format!("bad grid axes: {} {}", x_axis, y_axis));
+    }
+    if !(1..=x_axis.min(y_axis)).contains(&window) {
+        return Err(format!("bad grid window: {}", window));
+    }
+    let elapsed = field("elapsed")?
+        .parse()
+        .ok()
+        .and_then(|x| Duration::try_from_secs_f64(x).ok())
+        .ok_or(format!("bad elapsed: {}", field("elapsed")?))?;
+
+    // Individuals are scored as they are read, so the scoring settings have to come first.  Older
+    // files scored with the footrule, the default targets and no cards.
+    let mut rankings = rankings.clone();
+    if let Some(metric) = header.get("metric") {
+        rankings.metric = metric::parse_metric(metric)?;
+    }
+    if let Some(targets) = header.get("targets") {
+        rankings.targets = Targets::parse(targets)?;
+    }
+    if let Some(pairing) = header.get("pairs") {
+        rankings.pairing = cards::parse_pairing(pairing, rankings.names())?;
+    }
+    let mut pop = Vec::new();
+    let mut famous = Vec::new();
+    for chunk in chunks {
+        let (kind, ind) = parse_individual(chunk, &rankings)?;
+        if !(0..x_axis).contains(&ind.x) || !(0..y_axis).contains(&ind.y) {
+            return Err(format!(
+                "individual {} lies outside the grid at {} {}",
+                ind.id, ind.x, ind.y
+            ));
+        }
+        if let Some(violation) = ind.violations().first() {
+            return Err(format!(
+                "individual {} breaks a rule: {}",
+                ind.id, violation
+            ));
+        }
+        if let Some((name, attr)) = locks.mismatch(&ind) {
+            return Err(format!(
+                "individual {} does not match the locked {} {}",
+                ind.id, name, attr
+            ));
+        }
+        if kind == "famous" {
+            famous.push(ind);
+        } else {
+            pop.push(ind);
+        }
+    }
+    if pop.is_empty() {
+        return Err("no individuals".to_string());
+    }
+
+    let grid = CellularGa {
+        members: pop,
+        x_axis,
+        y_axis,
+        window,
+        counter: number("counter")? as usize,
+    };
+    let betrayal = Betrayal {
+        rankings,
+        locks: locks.clone(),
+    };
+    let mut pop = Population::from_grid(grid, betrayal, StdRng::seed_from_u64(number("seed")?));
+    pop.generation = number("generation")? as usize;
+    pop.elapsed = elapsed;
+    // Files from before strategies were configurable use the defaults
+    if let Some(selection) = header.get("selection") {
+        pop.selection = ga::parse_selection(selection)?;
+    }
+    if let Some(replacement) = header.get("replacement") {
+        pop.replacement = ga::parse_replacement(replacement)?;
+    }
+    if let Some(solver) = header.get("solver") {
+        pop.solver = trajectory::parse_solver(solver)?;
+    }
+    // Older files have no hall of fame, so it restarts from the current grid
+    if let Some(capacity) = header.get("hall_of_fame") {
+        let capacity = capacity
+            .parse()
+            .map_err(|_| format!("bad hall_of_fame: {}", capacity))?;
+        pop.hall_of_fame = HallOfFame::new(capacity);
+        for ind in famous.iter().chain(pop.grid.members.iter()) {
+            pop.hall_of_fame.consider(ind);
+        }
+    }
+    Ok(pop)
+}
+
+fn write_individual(text: &mut String, kind: &str, ind: &Individual) {
+    text.push_str(&format!("\n{} {} {} {}\n", kind, ind.id, ind.x, ind.y));
+    for name in ind.names() {
+        text.push_str(&name);
+        for attr in TRAITS.iter() {
+            let tup = ind.track(&name, attr).unwrap();
+            let values: Vec<String> = tup.0.iter().map(|x| x.to_string()).collect();
+            text.push_str(&format!(" {}={}@{}", attr, values.join(","), tup.1));
+        }
+        text.push('\n');
+    }
+}
+
+// Parse an "individual" or "famous" block, returning which it was
+fn parse_individual<'a>(
+    chunk: &'a str,
+    rankings: &Rankings,
+) -> Result<(&'a str, Individual), String> {
+    let mut lines = chunk.lines();
+    let header = lines.next().unwrap();
+    let (kind, rest) = header
+        .split_once(' ')
+        .filter(|x| x.0 == "individual" || x.0 == "famous")
+        .ok_or(format!("expected an individual: {}", header))?;
+    let bad = || format!("bad individual: {}", header);
+    let [id, x, y] = rest.split_whitespace().collect::<Vec<_>>()[..] else {
+        return Err(bad());
+    };
+    let id: usize = id.parse().map_err(|_| bad())?;
+    // Positions are kept as i32, so anything wider is an error rather than cut short
+    let x: i32 = x.parse().map_err(|_| bad())?;
+    let y: i32 = y.parse().map_err(|_| bad())?;
+
+    let mut chars = HashMap::new();
+    for line in lines {
+        // The four tracks come last, so a name may hold spaces
+        let mut name = line.trim_end();
+        let mut stats = HashMap::new();
+        for _ in TRAITS.iter() {
+            let (rest, word) = name
+                .rsplit_once(' ')
+                .ok_or(format!("{} is missing a trait", name))?;
+            let track = parse_track(word).ok_or(format!("bad track for {}: {}", rest, word))?;
+            stats.insert(track.0, track.1);
+            name = rest;
+        }
+        if !TRAITS.iter().all(|x| stats.contains_key(*x)) {
+            return Err(format!("{} is missing a trait", name));
+        }
+        chars.insert(name.to_string(), stats);
+    }
+    // Scoring looks every character up in the rankings, so they must match before it
+    let names = rankings.names();
+    if chars.len() != names.len() || !names.iter().all(|x| chars.contains_key(x)) {
+        return Err(format!(
+            "individual {} has different characters from the rankings",
+            id
+        ));
+    }
+
+    Ok((kind, Individual::from_chars(x, y, id, chars, rankings)))
+}
+
+// Parse "Might=2,2,3,4,5,6,7,8@2"
+fn parse_track(word: &str) -> Option<(String, Track)> {
+    let (attr, rest) = word.split_once('=')?;
+    let (values, index) = rest.split_once('@')?;
+    let track: Vec<i32> = values
+        .split(',')
+        .map(|x| x.parse().ok())
+        .collect::<Option<_>>()?;
+    let index: usize = index.parse().ok()?;
+    if !TRAITS.contains(&attr) || track.len() != 8 || index > 7 {
+        return None;
+    }
+
+    Some((attr.to_string(), (track, index)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RANKINGS: &str = "Might:\nKyle Smith\nAnna\nBo\n\nSpeed:\nBo\nKyle Smith\nAnna\n\n\
+        Know:\nAnna\nBo\nKyle Smith\n\nSanity:\nKyle Smith\nBo\nAnna\n";
+
+    fn population(rankings: &Rankings) -> Population {
+        let betrayal = Betrayal {
+            rankings: rankings.clone(),
+            locks: Locks::default(),
+        };
+        let mut rng = StdRng::seed_from_u64(7);
+        let grid = CellularGa::new(20, (10, 10, 3), &betrayal, &betrayal, &mut rng);
+        Population::from_grid(grid, betrayal, rng)
+    }
+
+    fn file(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("checkpoint-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name).to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn round_trip() {
+        let rankings = Rankings::parse(RANKINGS).unwrap();
+        let mut pop = population(&rankings);
+        pop.generation = 42;
+        let file = file("round_trip.txt");
+        save(&mut pop, &file).unwrap();
+
+        let loaded = load(&file, &rankings, &Locks::default()).unwrap();
+        assert_eq!(loaded.generation, 42);
+        assert_eq!(loaded.grid.counter, pop.grid.counter);
+        assert_eq!(loaded.grid.members.len(), pop.grid.members.len());
+        for (a, b) in pop.grid.members.iter().zip(loaded.grid.members.iter()) {
+            assert_eq!((a.id, a.x, a.y, a.fitness), (b.id, b.x, b.y, b.fitness));
+            for name in a.names() {
+                for attr in TRAITS.iter() {
+                    assert_eq!(a.track(&name, attr), b.track(&name, attr));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn renamed_character_is_an_error() {
+        let rankings = Rankings::parse(RANKINGS).unwrap();
+        let mut pop = population(&rankings);
+        let file = file("renamed.txt");
+        save(&mut pop, &file).unwrap();
+
+        let renamed = Rankings::parse(&RANKINGS.replace("Anna", "Ann")).unwrap();
+        let error = load(&file, &renamed, &Locks::default()).err().unwrap();
+        assert!(error.contains("different characters"), "{}", error);
+    }
+
+    // Save a population, swap the first line starting with `from` for `to` and load it again
+    fn corrupt(name: &str, from: &str, to: &str) -> Result<Population, String> {
+        let rankings = Rankings::parse(RANKINGS).unwrap();
+        let mut pop = population(&rankings);
+        let file = file(name);
+        save(&mut pop, &file).unwrap();
+        let text = fs::read_to_string(&file).unwrap();
+        let line = text.lines().find(|x| x.starts_with(from)).unwrap();
+        fs::write(&file, text.replacen(line, to, 1)).unwrap();
+        load(&file, &rankings, &Locks::default())
+    }
+
+    #[test]
+    fn bad_elapsed_is_an_error() {
+        for elapsed in ["-1", "NaN", "inf", "1e300"] {
+            let result = corrupt(
+                "elapsed.txt",
+                "elapsed 0.000",
+                &format!("elapsed {}", elapsed),
+            );
+            assert!(result.is_err(), "{}", elapsed);
+        }
+    }
+
+    #[test]
+    fn bad_grid_is_an_error() {
+        for grid in [
+            "0 10 3",
+            "10 -5 3",
+            "10 10 0",
+            "10 10 11",
+            "10 10",
+            "2147483647 10 3",
+        ] {
+            let result = corrupt("grid.txt", "grid 10 10 3", &format!("grid {}", grid));
+            assert!(result.is_err(), "{}", grid);
+        }
+    }
+
+    #[test]
+    fn bad_positions_are_errors() {
+        let moved = |position: &str| {
+            let to = format!("individual 0 {}", position);
+            corrupt("position.txt", "individual 0 ", &to)
+        };
+        assert!(moved("9 9").is_ok());
+        for position in ["4294967296 1", "1 -1", "10 1", "1.5 1", "1"] {
+            assert!(moved(position).is_err(), "{}", position);
+        }
+    }
+}
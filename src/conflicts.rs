@@ -0,0 +1,306 @@
+//! Why the requested rankings cannot all be met.
+//!
+//! Every character is held to the same rules, so without locks any single ranking can be met on
+//! its own.  What the rules do limit is how strong one character can be in several traits at
+//! once: starting values add up to 15, the indexes to a constitution of 10 or 11 and the tracks
+//! to a total of 145 to 155.  Locks narrow that further.  A conflict is a pair of characters and
+//! the fewest traits in which the first is requested above the second but no two legal sheets
+//! put it there in all of them at once, so at least one of those rankings must break.
+//!
+//! A rank-only run of the [`crate::exact`] search then bounds the rank error any legal roster can
+//! reach.  Rank error a roster has beyond that bound is not forced by the rules: it is traded for
+//! the averages, diversity or card pairs, or the search has not found the better roster yet.
+
+use crate::exact::{self, Solution};
+use crate::individual::Individual;
+use crate::population::Betrayal;
+use crate::progress::Progress;
+use crate::rules::{MAX_TOTAL, MIN_TOTAL, STARTING_SUM, TRAITS};
+use std::time::Duration;
+
+/// Traits in which one character is requested above another but cannot be above it in all of
+/// them at once.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub above: String,
+    pub below: String,
+    pub traits: Vec<&'static str>,
+    /// Why, in words
+    pub reason: String,
+}
+
+/// The conflicts of a set of rankings and the least rank error they allow.
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub conflicts: Vec<Conflict>,
+    /// A rank-only exact search, whose `lower` bounds the rank error of every legal roster
+    pub rank: Solution,
+    // The requested order of each trait
+    requested: Vec<Vec<String>>,
+}
+
+/// Find every conflict, then search for the least rank error for up to `budget`, or until
+/// Ctrl-C, reporting as `progress` asks.  `incumbent`, such as the population's best, gives the
+/// search a roster to beat.
+pub fn analyse(
+    betrayal: &Betrayal,
+    incumbent: Option<&Individual>,
+    budget: Duration,
+    progress: Progress,
+) -> Analysis {
+    let names = betrayal.rankings.names();
+    let ranges: Vec<Ranges> = names
+        .iter()
+        .map(|name| Ranges::new(exact::strength_ranges(name, betrayal)))
+        .collect();
+    let requested: Vec<[usize; 4]> = names
+        .iter()
+        .map(|name| {
+            TRAITS.map(|attr| {
+                let ranking = betrayal.rankings.get(attr).unwrap();
+                ranking.iter().position(|x| x == name).unwrap()
+            })
+        })
+        .collect();
+
+    let mut conflicts = Vec::new();
+    for a in 0..names.len() {
+        for b in (0..names.len()).filter(|b| *b != a) {
+            let wanted: Vec<usize> = (0..4)
+                .filter(|i| requested[a][*i] < requested[b][*i])
+                .collect();
+            // Smallest sets first, skipping any that contain a conflict already found
+            let mut found: Vec<usize> = Vec::new();
+            let mut subsets: Vec<usize> = (1..1usize << wanted.len()).collect();
+            subsets.sort_by_key(|x| x.count_ones());
+            for subset in subsets {
+                let traits: Vec<usize> = (0..wanted.len())
+                    .filter(|k| subset & 1 << k != 0)
+                    .map(|k| wanted[k])
+                    .collect();
+                let mask = traits.iter().fold(0, |mask, i| mask | 1 << i);
+                if found.iter().any(|x| x & mask == *x) || ranges[a].beats(&ranges[b], &traits) {
+                    continue;
+                }
+                found.push(mask);
+                conflicts.push(Conflict {
+                    above: names[a].clone(),
+                    below: names[b].clone(),
+                    traits: traits.iter().map(|i| TRAITS[*i]).collect(),
+                    reason: reason(
+                        betrayal, &names[a], &names[b], &traits, &ranges[a], &ranges[b],
+                    ),
+                });
+            }
+        }
+    }
+
+    Analysis {
+        conflicts,
+        rank: exact::solve_rank(betrayal, incumbent, budget, progress),
+        requested: TRAITS
+            .iter()
+            .map(|attr| betrayal.rankings.get(attr).unwrap().clone())
+            .collect(),
+    }
+}
+
+// The strongest and weakest a character can be in each trait, kept to the combinations no other
+// combination beats in every trait
+struct Ranges {
+    highs: Vec<[f32; 4]>,
+    lows: Vec<[f32; 4]>,
+}
+
+impl Ranges {
+    fn new(options: Vec<[(f32, f32); 4]>) -> Ranges {
+        let highs: Vec<[f32; 4]> = options.iter().map(|x| x.map(|r| r.1)).collect();
+        let lows: Vec<[f32; 4]> = options.iter().map(|x| x.map(|r| -r.0)).collect();
+        Ranges {
+            highs: frontier(highs),
+            lows: frontier(lows).into_iter().map(|x| x.map(|v| -v)).collect(),
+        }
+    }
+
+    // Whether some sheet of this character can be stronger than some sheet of `other` in every
+    // one of `traits` at once
+    fn beats(&self, other: &Ranges, traits: &[usize]) -> bool {
+        self.highs.iter().any(|high| {
+            other
+                .lows
+                .iter()
+                .any(|low| traits.iter().all(|i| high[*i] > low[*i]))
+        })
+    }
+
+    fn strongest(&self, i: usize) -> f32 {
+        self.highs
+            .iter()
+            .map(|x| x[i])
+            .fold(f32::NEG_INFINITY, f32::max)
+    }
+
+    fn weakest(&self, i: usize) -> f32 {
+        self.lows.iter().map(|x| x[i]).fold(f32::INFINITY, f32::min)
+    }
+}
+
+// The points no other point matches or beats in every coordinate
+fn frontier(mut points: Vec<[f32; 4]>) -> Vec<[f32; 4]> {
+    points.sort_by(|a, b| b.partial_cmp(a).unwrap());
+    points.dedup();
+    let mut kept: Vec<[f32; 4]> = Vec::new();
+    for point in points {
+        if !kept.iter().any(|x| (0..4).all(|i| x[i] >= point[i])) {
+            kept.push(point);
+        }
+    }
+    kept
+}
+
+fn reason(
+    betrayal: &Betrayal,
+    above: &str,
+    below: &str,
+    traits: &[usize],
+    high: &Ranges,
+    low: &Ranges,
+) -> String {
+    let locked = |name: &str| {
+        TRAITS
+            .iter()
+            .filter(|attr| betrayal.locks.is_locked(name, attr))
+            .copied()
+            .collect::<Vec<_>>()
+    };
+    let describe = |name: &str| match locked(name).as_slice() {
+        [] => String::new(),
+        traits => format!(" with {} locked", traits.join(" and ")),
+    };
+
+    if let [i] = traits {
+        return format!(
+            "{}{} reaches at most {:.2} in {}, but {}{} is at least {:.2}",
+            above,
+            describe(above),
+            high.strongest(*i),
+            TRAITS[*i],
+            below,
+            describe(below),
+            low.weakest(*i)
+        );
+    }
+    let names: Vec<&str> = traits.iter().map(|i| TRAITS[*i]).collect();
+    format!(
+        "{}{} cannot beat {}{} in {} at once: starting values add up to {}, constitution is 10 \
+         or 11 and track totals lie between {} and {}",
+        above,
+        describe(above),
+        below,
+        describe(below),
+        names.join(" and "),
+        STARTING_SUM,
+        MIN_TOTAL,
+        MAX_TOTAL
+    )
+}
+
+impl Analysis {
+    /// The conflicts, the rank error bound and, with `best`, which of its inversions are forced
+    /// and which are not.
+    pub fn report(&self, best: Option<&Individual>) -> String {
+        let mut text = String::new();
+        if self.conflicts.is_empty() {
+            text.push_str("No two characters conflict in any set of traits\n");
+        } else {
+            text.push_str("Conflicts:\n");
+            for conflict in self.conflicts.iter() {
+                text.push_str(&format!(
+                    "\t{} above {} in {}: {}\n",
+                    conflict.above,
+                    conflict.below,
+                    conflict.traits.join(", "),
+                    conflict.reason
+                ));
+            }
+        }
+
+        let rank = &self.rank;
+        text.push_str(&format!(
+            "Searched {} nodes{}\n",
+            rank.nodes,
+            if rank.complete {
+                ""
+            } else {
+                " before running out of time, so the bound may be loose"
+            }
+        ));
+        if rank.gap() == 0 {
+            text.push_str(&format!(
+                "Least rank error of any legal roster: {}\n",
+                rank.lower
+            ));
+        } else if rank.upper == i32::MAX {
+            text.push_str(&format!(
+                "No legal roster has rank error below {}, and none was found\n",
+                rank.lower
+            ));
+        } else {
+            text.push_str(&format!(
+                "No legal roster has rank error below {}, the least found is {}\n",
+                rank.lower, rank.upper
+            ));
+        }
+        if let Some(ind) = rank.best.as_ref() {
+            text.push_str(&format!(
+                "Roster with rank error {} found, fitness {}\n",
+                ind.rank_score, ind.fitness
+            ));
+        }
+
+        let Some(ind) = best else {
+            return text;
+        };
+        text.push_str(&format!(
+            "Population best has rank error {}, {} above the bound\n",
+            ind.rank_score,
+            ind.rank_score - rank.lower
+        ));
+        for (attr, ranking) in self.inversions(ind) {
+            for (a, b) in ranking {
+                let forced = self
+                    .conflicts
+                    .iter()
+                    .find(|x| x.above == a && x.below == b && x.traits.contains(&attr));
+                let why = match forced {
+                    Some(x) if x.traits.len() == 1 => "forced".to_string(),
+                    Some(x) => format!("one of {} must break", x.traits.join(", ")),
+                    None => "not forced by the rules".to_string(),
+                };
+                text.push_str(&format!("\t{}: {} below {}, {}\n", attr, a, b, why));
+            }
+        }
+        text
+    }
+
+    // For each trait, the pairs of characters ranked the other way round from the request
+    fn inversions(&self, ind: &Individual) -> Vec<(&'static str, Vec<(String, String)>)> {
+        TRAITS
+            .iter()
+            .zip(self.requested.iter())
+            .map(|(attr, requested)| {
+                let actual = ind.order(attr);
+                let mut pairs = Vec::new();
+                for (p, a) in requested.iter().enumerate() {
+                    for b in requested[p + 1..].iter() {
+                        let position = |x: &String| actual.iter().position(|y| y == x);
+                        if position(a) > position(b) {
+                            pairs.push((a.clone(), b.clone()));
+                        }
+                    }
+                }
+                (*attr, pairs)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,193 @@
+//! A live view of a run, redrawn in place instead of one line per generation.
+//!
+//! It shows the generation, the best and mean fitness with a sparkline of the best, the time left,
+//! and the best individual's breakdown and sheets.  Keys act straight away: `p` pauses and
+//! resumes, `s` saves a checkpoint and `q` stops the run as Ctrl-C would.  Reading single keys
+//! takes the terminal out of line mode, which is done with `stty`, so the dashboard only starts
+//! where `stty` works on stdin.  It is one of the [`crate::progress::Progress`] settings.
+
+use crate::individual::Individual;
+use crate::progress::{self, Frame};
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+// Least time between redraws while running
+const REFRESH: Duration = Duration::from_millis(200);
+// Best fitnesses kept for the sparkline, one per redraw
+const HISTORY: usize = 60;
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+// Sheets side by side
+const COLUMNS: usize = 3;
+
+/// What a key asked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Pause,
+    Save,
+    Stop,
+}
+
+/// The terminal while a dashboard owns it.  Dropping it gives the terminal back as it was.
+pub struct Dashboard {
+    // stty settings to restore
+    saved: String,
+    history: Vec<i32>,
+    drawn: Option<Instant>,
+    pub paused: bool,
+    /// Shown under the header until the next one replaces it
+    pub note: String,
+}
+
+impl Dashboard {
+    /// Take over the terminal.  Fails when stdin is not a terminal `stty` can switch.
+    pub fn start() -> Result<Dashboard, String> {
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "min", "0", "time", "0"])?;
+        // Alternate screen, cursor hidden
+        print!("\x1b[?1049h\x1b[?25l");
+        Ok(Dashboard {
+            saved: saved.trim().to_string(),
+            history: Vec::new(),
+            drawn: None,
+            paused: false,
+            note: "p pause, s save checkpoint, q stop".to_string(),
+        })
+    }
+
+    /// The next key pressed since the last call, ignoring keys that mean nothing.
+    pub fn poll(&mut self) -> Option<Key> {
+        let mut byte = [0u8];
+        while io::stdin().read(&mut byte).ok()? == 1 {
+            let key = match byte[0] {
+                b'p' | b' ' => Key::Pause,
+                b's' => Key::Save,
+                b'q' => Key::Stop,
+                _ => continue,
+            };
+            // Whatever the key does shows on the next redraw
+            self.drawn = None;
+            return Some(key);
+        }
+        None
+    }
+
+    /// Redraw, unless the last redraw was too recent and nothing happened since.
+    pub fn draw(&mut self, frame: &Frame) {
+        if self.drawn.is_some_and(|x| x.elapsed() < REFRESH) {
+            return;
+        }
+        self.drawn = Some(Instant::now());
+        if !self.paused {
+            self.history.push(frame.best.fitness);
+            if self.history.len() > HISTORY {
+                self.history.remove(0);
+            }
+        }
+
+        let mut lines = vec![
+            format!(
+                "Generation {} of {}{}",
+                frame.generation,
+                frame.generations,
+                if self.paused { "  PAUSED" } else { "" }
+            ),
+            self.note.clone(),
+            String::new(),
+            format!(
+                "Best fitness {}, mean {:.1}  {}",
+                frame.best.fitness,
+                frame.mean(),
+                self.sparkline()
+            ),
+            format!(
+                "Elapsed {}, {:.0} generations/s, {}",
+                progress::clock(frame.elapsed),
+                frame.rate(),
+                progress::left(frame.eta())
+            ),
+            frame.best.breakdown(),
+            String::new(),
+        ];
+        lines.extend(sheets(frame.best));
+
+        let mut screen = String::from("\x1b[H");
+        for line in lines {
+            screen.push_str(&line);
+            screen.push_str("\x1b[K\n");
+        }
+        screen.push_str("\x1b[J");
+        print!("{}", screen);
+        io::stdout().flush().ok();
+    }
+
+    fn sparkline(&self) -> String {
+        let low = self.history.iter().copied().min().unwrap_or(0);
+        let high = self.history.iter().copied().max().unwrap_or(0);
+        let span = (high - low).max(1) as usize;
+        self.history
+            .iter()
+            .map(|x| BARS[(*x - low) as usize * (BARS.len() - 1) / span])
+            .collect()
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        io::stdout().flush().ok();
+        stty(&[&self.saved]).ok();
+    }
+}
+
+// Run stty on the terminal and return what it printed
+fn stty(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("Could not run stty: {}", e))?;
+    if !output.status.success() {
+        return Err("stdin is not a terminal".to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// Every character's sheet, a few side by side
+fn sheets(ind: &Individual) -> Vec<String> {
+    let blocks: Vec<Vec<String>> = ind
+        .names()
+        .iter()
+        .map(|name| {
+            let mut lines = vec![format!("{}:", name)];
+            lines.extend(ind.sheet(name).lines().map(|x| x.to_string()));
+            lines
+        })
+        .collect();
+    let width = blocks
+        .iter()
+        .flatten()
+        .map(|x| x.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut lines = Vec::new();
+    for row in blocks.chunks(COLUMNS) {
+        let height = row.iter().map(|x| x.len()).max().unwrap();
+        for k in 0..height {
+            let line: Vec<String> = row
+                .iter()
+                .map(|block| {
+                    format!(
+                        "{:<width$}",
+                        block.get(k).map_or("", |x| x.as_str()),
+                        width = width
+                    )
+                })
+                .collect();
+            lines.push(line.join("   ").trim_end().to_string());
+        }
+        lines.push(String::new());
+    }
+    lines
+}
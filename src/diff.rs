@@ -0,0 +1,226 @@
+//! What changed between two individuals, character by character and trait by trait.
+//!
+//! A trait counts as changed when its track values differ, its starting index moved, or the
+//! character's place in that trait's order moved, which can happen without touching the
+//! character at all when a rival changed.  The result prints as a terminal table, optionally
+//! colored, or as JSON for other tools.
+
+use crate::individual::Individual;
+use crate::rules::{Track, TRAITS};
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// One trait of one character that differs between the two individuals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraitDiff {
+    pub name: String,
+    pub attr: &'static str,
+    pub before: Track,
+    pub after: Track,
+    /// Place in the trait's order, strongest first, counting from 1
+    pub rank_before: usize,
+    pub rank_after: usize,
+}
+
+impl TraitDiff {
+    pub fn values_changed(&self) -> bool {
+        self.before.0 != self.after.0
+    }
+
+    pub fn index_moved(&self) -> bool {
+        self.before.1 != self.after.1
+    }
+
+    pub fn rank_moved(&self) -> bool {
+        self.rank_before != self.rank_after
+    }
+}
+
+/// Every difference between two individuals with the same characters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diff {
+    /// Id and fitness of each individual
+    pub before: (usize, i32),
+    pub after: (usize, i32),
+    /// Changed traits, by character name then in [`TRAITS`] order
+    pub traits: Vec<TraitDiff>,
+}
+
+/// Compare two individuals.  Fails if they do not have the same characters.
+pub fn diff(before: &Individual, after: &Individual) -> Result<Diff, String> {
+    let names = before.names();
+    if names != after.names() {
+        return Err("The individuals have different characters".to_string());
+    }
+
+    let orders: Vec<(Vec<String>, Vec<String>)> = TRAITS
+        .iter()
+        .map(|attr| (before.order(attr), after.order(attr)))
+        .collect();
+    let rank = |order: &[String], name: &str| order.iter().position(|x| x == name).unwrap() + 1;
+
+    let mut traits = Vec::new();
+    for name in names.iter() {
+        for (attr, (order_before, order_after)) in TRAITS.iter().zip(orders.iter()) {
+            let change = TraitDiff {
+                name: name.clone(),
+                attr,
+                before: before.track(name, attr).unwrap().clone(),
+                after: after.track(name, attr).unwrap().clone(),
+                rank_before: rank(order_before, name),
+                rank_after: rank(order_after, name),
+            };
+            if change.values_changed() || change.index_moved() || change.rank_moved() {
+                traits.push(change);
+            }
+        }
+    }
+
+    Ok(Diff {
+        before: (before.id, before.fitness),
+        after: (after.id, after.fitness),
+        traits,
+    })
+}
+
+impl Diff {
+    /// A table with a before and an after line per changed trait.  With `color`, changed values
+    /// and the moved starting value are yellow, and rank moves green when up and red when down.
+    pub fn table(&self, color: bool) -> String {
+        let paint = |text: String, code: &str, on: bool| {
+            if color && on {
+                format!("{}{}{}", code, text, RESET)
+            } else {
+                text
+            }
+        };
+
+        let mut text = format!(
+            "Individual {} (fitness {}) -> individual {} (fitness {})\n",
+            self.before.0, self.before.1, self.after.0, self.after.1
+        );
+        if self.traits.is_empty() {
+            text.push_str("No differences\n");
+            return text;
+        }
+
+        let width = self.traits.iter().map(|x| x.name.len()).max().unwrap();
+        let mut last = "";
+        for change in self.traits.iter() {
+            let name = if change.name == last {
+                ""
+            } else {
+                &change.name
+            };
+            last = &change.name;
+
+            let before = values(&change.before, &change.after, |x, _| x.to_string());
+            let after = values(&change.after, &change.before, |x, changed| {
+                paint(x.to_string(), YELLOW, changed)
+            });
+            let moved = change.rank_before as i32 - change.rank_after as i32;
+            let rank = match moved {
+                0 => format!("rank {}", change.rank_after),
+                _ => paint(
+                    format!("rank {} ({:+})", change.rank_after, moved),
+                    if moved > 0 { GREEN } else { RED },
+                    true,
+                ),
+            };
+
+            text.push_str(&format!(
+                "{:<width$}  {:<6}  {}   rank {}\n",
+                name,
+                change.attr,
+                before,
+                change.rank_before,
+                width = width
+            ));
+            text.push_str(&format!(
+                "{:<width$}  {:<6}  {}   {}\n",
+                "",
+                "",
+                after,
+                rank,
+                width = width
+            ));
+        }
+        text
+    }
+
+    /// The same differences as a JSON object.
+    pub fn json(&self) -> String {
+        let track = |tup: &Track| {
+            let values: Vec<String> = tup.0.iter().map(|x| x.to_string()).collect();
+            format!(
+                "{{\"values\": [{}], \"index\": {}}}",
+                values.join(", "),
+                tup.1
+            )
+        };
+        let traits: Vec<String> = self
+            .traits
+            .iter()
+            .map(|x| {
+                format!(
+                    "    {{\"character\": {}, \"trait\": \"{}\", \"before\": {}, \"after\": {}, \"rank_before\": {}, \"rank_after\": {}}}",
+                    quote(&x.name),
+                    x.attr,
+                    track(&x.before),
+                    track(&x.after),
+                    x.rank_before,
+                    x.rank_after
+                )
+            })
+            .collect();
+        let changes = match traits.is_empty() {
+            true => "[]".to_string(),
+            false => format!("[\n{}\n  ]", traits.join(",\n")),
+        };
+        format!(
+            "{{\n  \"before\": {{\"id\": {}, \"fitness\": {}}},\n  \"after\": {{\"id\": {}, \"fitness\": {}}},\n  \"changes\": {}\n}}\n",
+            self.before.0,
+            self.before.1,
+            self.after.0,
+            self.after.1,
+            changes
+        )
+    }
+}
+
+// A track in sheet form, each value passed through `show` with whether it differs from `other`
+fn values(tup: &Track, other: &Track, show: impl Fn(i32, bool) -> String) -> String {
+    let values: Vec<String> = tup
+        .0
+        .iter()
+        .zip(other.0.iter())
+        .enumerate()
+        .map(|(i, (x, y))| {
+            let value = show(*x, x != y || (i == tup.1) != (i == other.1));
+            if i == tup.1 {
+                format!("[{}]", value)
+            } else {
+                format!(" {} ", value)
+            }
+        })
+        .collect();
+    values.join("")
+}
+
+// A JSON string literal
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,162 @@
+use crate::ask;
+use betrayal_characters::rules::{Track, TRAITS};
+use betrayal_characters::{Individual, Population};
+
+const HELP: &str = "Commands:\n\
+    c <name>             Select a character\n\
+    t <trait>            Select a trait of the selected character\n\
+    v <position> <value> Set a track value, positions 1 to 8\n\
+    i <position>         Set the starting position, 3 to 5\n\
+    s                    Show the selected character\n\
+    done                 Inject the edited individual into the population\n\
+    quit                 Discard the edits";
+
+// Hand-edit a copy of an individual.  The copy is rescored after every change and only goes back
+// into the population, replacing the current worst individual, when it breaks no rules.
+pub fn edit(pop: &mut Population) {
+    let response = ask("Edit which individual id? (blank for the best) ");
+    let original = if response.is_empty() {
+        Some(pop.best())
+    } else {
+        match response.parse::<usize>() {
+            Ok(id) => pop.grid.members.iter().find(|x| x.id == id),
+            Err(e) => {
+                println!("Not a recognized response: {}. Error: {}", response, e);
+                return;
+            }
+        }
+    };
+    let mut ind = match original {
+        Some(ind) => ind.clone(),
+        None => {
+            println!("No individual with id {}", response);
+            return;
+        }
+    };
+
+    println!("Editing individual {}", ind.id);
+    println!("Characters: {}", ind.names().join(", "));
+    println!("{}", HELP);
+    report(&ind);
+
+    let mut name: Option<String> = None;
+    let mut attr: Option<String> = None;
+    loop {
+        let prompt = match (&name, &attr) {
+            (Some(name), Some(attr)) => format!("{} {}> ", name, attr),
+            (Some(name), None) => format!("{}> ", name),
+            _ => "> ".to_string(),
+        };
+        let response = ask(&prompt);
+        let words: Vec<&str> = response.split_whitespace().collect();
+
+        match words.as_slice() {
+            ["c", new_name] => match ind.names().into_iter().find(|x| x == new_name) {
+                Some(new_name) => {
+                    print!("{}", ind.sheet(&new_name));
+                    name = Some(new_name);
+                    attr = None;
+                }
+                None => println!("No character named {}", new_name),
+            },
+            ["t", new_attr] => match TRAITS.iter().find(|x| *x == new_attr) {
+                Some(new_attr) if name.is_some() => attr = Some(new_attr.to_string()),
+                Some(_) => println!("Select a character first"),
+                None => println!("Traits are {}", TRAITS.join(", ")),
+            },
+            ["v", position, value] => {
+                let position = parse_position(position);
+                let value = value.parse::<i32>().ok();
+                if let (Some(position), Some(value)) = (position, value) {
+                    if let Some(tup) = selected(pop, &mut ind, &name, &attr) {
+                        tup.0[position] = value;
+                        changed(pop, &mut ind, &name);
+                    }
+                } else {
+                    println!("Usage: v <position 1 to 8> <value>");
+                }
+            }
+            // The rules only let a track start at its third to fifth value
+            ["i", position] => match parse_position(position).filter(|x| (2..=4).contains(x)) {
+                Some(position) => {
+                    if let Some(tup) = selected(pop, &mut ind, &name, &attr) {
+                        tup.1 = position;
+                        changed(pop, &mut ind, &name);
+                    }
+                }
+                None => println!("Usage: i <position 3 to 5>"),
+            },
+            ["s"] => match &name {
+                Some(name) => print!("{}", ind.sheet(name)),
+                None => println!("Select a character first"),
+            },
+            ["done"] => {
+                if !ind.violations().is_empty() {
+                    println!("Fix the rule violations before injecting the individual");
+                    continue;
+                }
+                inject(pop, ind);
+                return;
+            }
+            ["quit"] => return,
+            _ => println!("{}", HELP),
+        }
+    }
+}
+
+fn parse_position(position: &str) -> Option<usize> {
+    match position.parse::<usize>() {
+        Ok(position) if (1..=8).contains(&position) => Some(position - 1),
+        _ => None,
+    }
+}
+
+fn selected<'a>(
+    pop: &Population,
+    ind: &'a mut Individual,
+    name: &Option<String>,
+    attr: &Option<String>,
+) -> Option<&'a mut Track> {
+    let (name, attr) = match (name, attr) {
+        (Some(name), Some(attr)) => (name, attr),
+        _ => {
+            println!("Select a character and a trait first");
+            return None;
+        }
+    };
+    if pop.betrayal.locks.is_locked(name, attr) {
+        println!("{} {} is locked", name, attr);
+        return None;
+    }
+    ind.track_mut(name, attr)
+}
+
+fn changed(pop: &Population, ind: &mut Individual, name: &Option<String>) {
+    ind.score(&pop.betrayal.rankings);
+    if let Some(name) = name {
+        print!("{}", ind.sheet(name));
+    }
+    report(ind);
+}
+
+fn report(ind: &Individual) {
+    println!("{}", ind.breakdown());
+    let violations = ind.violations();
+    if violations.is_empty() {
+        println!("No rule violations");
+    } else {
+        println!("Rule violations:");
+        for violation in violations {
+            println!("\t{}", violation);
+        }
+    }
+}
+
+fn inject(pop: &mut Population, ind: Individual) {
+    let worst = pop.inject(ind);
+    println!(
+        "Injected individual {} in place of individual {}",
+        pop.grid.counter - 1,
+        worst
+    );
+}
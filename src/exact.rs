@@ -0,0 +1,933 @@
+//! Branch-and-bound search for the best possible roster, with a bound on how far from optimal
+//! the best find can be.
+//!
+//! Only a few things about a track matter to the fitness: its starting value and index, which
+//! feed the averages and the diversity term, and its strength, which decides the rankings.  The
+//! search therefore groups the legal tracks of each trait from [`crate::catalogue`] into classes
+//! sharing a starting value and index, and branches over one combination of classes per
+//! character.  At every node the averages and diversity term get their exact best over the
+//! characters still open, and the rank error is bounded below by the pairs of characters whose
+//! strength ranges force them out of the requested order.  Each surviving leaf is realized with
+//! real tracks, choosing strengths in the requested order where the classes allow it.
+//!
+//! A leaf whose tracks fall short of its bound, or a search cut short by the time budget, leaves a
+//! gap between the best roster found and the lowest fitness still possible.  A gap of zero proves
+//! the roster optimal.
+
+use crate::catalogue::Catalogue;
+use crate::individual::Individual;
+use crate::metric::RankMetric;
+use crate::polish;
+use crate::population::Betrayal;
+use crate::progress::{self, Progress, Reporter};
+use crate::rules::{self, Track, MAX_TOTAL, MIN_TOTAL, STARTING_SUM, TRAITS};
+use crate::stopping;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+/// What the exact search found.
+#[derive(Debug, Clone)]
+pub struct Solution {
+    /// The best roster found, when it beats the incumbent
+    pub best: Option<Individual>,
+    /// Fitness of the best roster known, found here or given as the incumbent
+    pub upper: i32,
+    /// No legal roster has a fitness below this
+    pub lower: i32,
+    /// Search nodes visited
+    pub nodes: u64,
+    /// Whether the whole tree was searched before the budget ran out
+    pub complete: bool,
+    /// Whether Ctrl-C stopped the search
+    pub interrupted: bool,
+}
+
+impl Solution {
+    /// How much better than `upper` a roster could still be.  Zero proves `upper` optimal.
+    pub fn gap(&self) -> i32 {
+        self.upper - self.lower
+    }
+}
+
+// Largest roster whose rank bound tracks every set of placed characters
+const PLACEMENT_LIMIT: usize = 12;
+
+// A track with its strength and total
+type Choice = (f32, i32, Track);
+
+// Tracks of one trait sharing a starting value and index
+struct Class {
+    value: i32,
+    index: usize,
+    low: f32,
+    high: f32,
+    min_total: i32,
+    max_total: i32,
+    // Weakest first
+    tracks: Vec<Choice>,
+    // Distinct strengths of the tracks, weakest first
+    strengths: Vec<f32>,
+}
+
+struct Character {
+    name: String,
+    // Requested position in each trait, 0 for the strongest
+    requested: [usize; 4],
+    classes: [Vec<Class>; 4],
+    // One class per trait for each legal combination, most promising first
+    options: Vec<[usize; 4]>,
+    min_value: [i32; 4],
+    max_value: [i32; 4],
+    max_fours: usize,
+    // Per trait, the weakest and strongest track over all options
+    weakest: [f32; 4],
+    strongest: [f32; 4],
+}
+
+struct Search<'a> {
+    betrayal: &'a Betrayal,
+    chars: Vec<Character>,
+    chosen: Vec<[usize; 4]>,
+    sums: [i32; 4],
+    tens: usize,
+    fours: usize,
+    best: Option<Individual>,
+    upper: i32,
+    // Lowest bound of any leaf or subtree left unresolved
+    open: i32,
+    // Average penalty of each trait for every possible sum of its starting values
+    penalties: Vec<Vec<i32>>,
+    nodes: u64,
+    deadline: Instant,
+    stopped: bool,
+    // Score leaves and bounds by rank error alone
+    rank_only: bool,
+    reporter: Reporter,
+    started: Instant,
+    budget: Duration,
+    // Bound at the root, the lowest score possible while the search is still open
+    root: i32,
+}
+
+/// Search for the best roster for up to `budget`, or until Ctrl-C, reporting on the way as
+/// `progress` asks.  `incumbent` is a roster already known, such as the population's best; only
+/// rosters beating it are reported.
+pub fn solve(
+    betrayal: &Betrayal,
+    incumbent: Option<&Individual>,
+    budget: Duration,
+    progress: Progress,
+) -> Solution {
+    let mut solution = run(
+        betrayal,
+        incumbent.map_or(i32::MAX, |x| x.fitness),
+        budget,
+        false,
+        progress,
+    );
+    // Polishing can only lower the fitness, and the bound still holds
+    if let Some(best) = solution.best.as_mut() {
+        polish::polish(best, betrayal);
+        solution.upper = best.fitness;
+        solution.lower = solution.lower.min(solution.upper);
+    }
+    solution
+}
+
+/// Search for the roster with the least rank error, ignoring the rest of the fitness.  `upper`
+/// and `lower` of the result are rank errors rather than fitnesses, so `lower` is a bound on
+/// the rank error any legal roster can reach.
+pub fn solve_rank(
+    betrayal: &Betrayal,
+    incumbent: Option<&Individual>,
+    budget: Duration,
+    progress: Progress,
+) -> Solution {
+    run(
+        betrayal,
+        incumbent.map_or(i32::MAX, |x| x.rank_score),
+        budget,
+        true,
+        progress,
+    )
+}
+
+fn run(
+    betrayal: &Betrayal,
+    upper: i32,
+    budget: Duration,
+    rank_only: bool,
+    progress: Progress,
+) -> Solution {
+    let mut search = Search::new(betrayal, upper, budget, rank_only, progress);
+    // Locks can leave a character with no legal sheet at all
+    if search.chars.iter().any(|c| c.options.is_empty()) {
+        return Solution {
+            best: None,
+            upper,
+            lower: upper,
+            nodes: 0,
+            complete: true,
+            interrupted: false,
+        };
+    }
+
+    search.root = search.bound(0);
+    let outer = stopping::start();
+    search.search(0);
+    let interrupted = stopping::interrupted();
+    stopping::finish(outer);
+    let lower = search.open.min(search.upper);
+    search.report(lower, true);
+    search.reporter.finish();
+
+    Solution {
+        lower,
+        best: search.best,
+        upper: search.upper,
+        nodes: search.nodes,
+        complete: !search.stopped,
+        interrupted,
+    }
+}
+
+fn character(name: &str, betrayal: &Betrayal) -> Character {
+    let catalogue = Catalogue::for_character(name, &betrayal.locks);
+    let classes = TRAITS.map(|attr| classes(catalogue.starts(attr)));
+
+    let mut options = Vec::new();
+    for m in 0..classes[0].len() {
+        for s in 0..classes[1].len() {
+            for k in 0..classes[2].len() {
+                for x in 0..classes[3].len() {
+                    let picked = [
+                        &classes[0][m],
+                        &classes[1][s],
+                        &classes[2][k],
+                        &classes[3][x],
+                    ];
+                    let value: i32 = picked.iter().map(|c| c.value).sum();
+                    let index: usize = picked.iter().map(|c| c.index).sum();
+                    let min_total: i32 = picked.iter().map(|c| c.min_total).sum();
+                    let max_total: i32 = picked.iter().map(|c| c.max_total).sum();
+                    if value == STARTING_SUM
+                        && (10..=11).contains(&index)
+                        && min_total <= MAX_TOTAL
+                        && max_total >= MIN_TOTAL
+                    {
+                        options.push([m, s, k, x]);
+                    }
+                }
+            }
+        }
+    }
+    // Starting values near the average targets in use and many index 4 starts first, to find
+    // good rosters early and prune harder
+    let targets = betrayal.rankings.targets.starts;
+    let promise = |option: &[usize; 4]| {
+        let mut distance = 0.0;
+        let mut fours = 0;
+        for (i, target) in targets.iter().enumerate() {
+            let class = &classes[i][option[i]];
+            distance += (class.value as f32 - target).abs();
+            fours += (class.index == 4) as i32;
+        }
+        (distance * 4.0) as i32 - fours
+    };
+    options.sort_by_key(promise);
+
+    let mut min_value = [i32::MAX; 4];
+    let mut max_value = [i32::MIN; 4];
+    let mut max_fours = 0;
+    let mut weakest = [f32::INFINITY; 4];
+    let mut strongest = [f32::NEG_INFINITY; 4];
+    for option in options.iter() {
+        for i in 0..4 {
+            let class = &classes[i][option[i]];
+            min_value[i] = min_value[i].min(class.value);
+            max_value[i] = max_value[i].max(class.value);
+            weakest[i] = weakest[i].min(class.low);
+            strongest[i] = strongest[i].max(class.high);
+        }
+        let fours = (0..4)
+            .filter(|i| classes[*i][option[*i]].index == 4)
+            .count();
+        max_fours = max_fours.max(fours);
+    }
+
+    let requested = TRAITS.map(|attr| {
+        let ranking = betrayal.rankings.get(attr).unwrap();
+        ranking.iter().position(|x| x == name).unwrap()
+    });
+
+    Character {
+        name: name.to_string(),
+        requested,
+        classes,
+        options,
+        min_value,
+        max_value,
+        max_fours,
+        weakest,
+        strongest,
+    }
+}
+
+// Put first the options whose strengths sit nearest each character's requested place.  Places
+// between the weakest and strongest reachable strengths are spread evenly, pinned to the strength
+// of any fully locked trait at its requested place.
+fn order_by_rank(chars: &mut [Character]) {
+    let n = chars.len();
+    let targets: Vec<Vec<f32>> = (0..4)
+        .map(|i| {
+            let weakest = chars
+                .iter()
+                .map(|c| c.weakest[i])
+                .fold(f32::INFINITY, f32::min);
+            let strongest = chars
+                .iter()
+                .map(|c| c.strongest[i])
+                .fold(f32::NEG_INFINITY, f32::max);
+            // Places from the top, with one beyond each end
+            let mut anchors: Vec<(f32, f32)> = vec![(-1.0, strongest), (n as f32, weakest)];
+            for c in chars.iter().filter(|c| c.weakest[i] == c.strongest[i]) {
+                anchors.push((c.requested[i] as f32, c.weakest[i]));
+            }
+            anchors.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            (0..n)
+                .map(|place| {
+                    let place = place as f32;
+                    let k = anchors.partition_point(|x| x.0 < place).max(1);
+                    let ((p, x), (q, y)) = (anchors[k - 1], anchors[k]);
+                    match q - p {
+                        0.0 => y,
+                        gap => x + (y - x) * (place - p) / gap,
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    for character in chars.iter_mut() {
+        let classes = &character.classes;
+        let requested = character.requested;
+        let distance = |option: &[usize; 4]| {
+            (0..4)
+                .map(|i| {
+                    let class = &classes[i][option[i]];
+                    let target = targets[i][requested[i]];
+                    (target.clamp(class.low, class.high) - target).abs()
+                })
+                .sum::<f32>()
+        };
+        character
+            .options
+            .sort_by(|a, b| distance(a).partial_cmp(&distance(b)).unwrap());
+    }
+}
+
+/// The weakest and strongest track of each trait for every legal combination of starting values
+/// and indexes a character can take, given its locks.
+pub(crate) fn strength_ranges(name: &str, betrayal: &Betrayal) -> Vec<[(f32, f32); 4]> {
+    let character = character(name, betrayal);
+    character
+        .options
+        .iter()
+        .map(|option| {
+            [0, 1, 2, 3].map(|i| {
+                let class = &character.classes[i][option[i]];
+                (class.low, class.high)
+            })
+        })
+        .collect()
+}
+
+fn classes(starts: &[Track]) -> Vec<Class> {
+    let mut groups: BTreeMap<(i32, usize), Vec<Choice>> = BTreeMap::new();
+    for tup in starts.iter() {
+        groups.entry((tup.0[tup.1], tup.1)).or_default().push((
+            rules::strength(tup),
+            tup.0.iter().sum(),
+            tup.clone(),
+        ));
+    }
+
+    groups
+        .into_iter()
+        .map(|((value, index), mut tracks)| {
+            tracks.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            let mut strengths: Vec<f32> = tracks.iter().map(|x| x.0).collect();
+            strengths.dedup();
+            Class {
+                value,
+                index,
+                low: tracks.first().unwrap().0,
+                high: tracks.last().unwrap().0,
+                min_total: tracks.iter().map(|x| x.1).min().unwrap(),
+                max_total: tracks.iter().map(|x| x.1).max().unwrap(),
+                tracks,
+                strengths,
+            }
+        })
+        .collect()
+}
+
+impl<'a> Search<'a> {
+    fn new(
+        betrayal: &'a Betrayal,
+        upper: i32,
+        budget: Duration,
+        rank_only: bool,
+        progress: Progress,
+    ) -> Search<'a> {
+        let mut chars: Vec<Character> = betrayal
+            .rankings
+            .names()
+            .iter()
+            .map(|name| character(name, betrayal))
+            .collect();
+        // Branch on the most constrained characters, usually the locked ones, first
+        chars.sort_by_key(|c| c.options.len());
+        if rank_only {
+            order_by_rank(&mut chars);
+        }
+
+        // The dashboard follows generations, which this search has none of
+        let progress = match progress {
+            Progress::Dashboard => Progress::Seconds(5.0),
+            progress => progress,
+        };
+        let n = chars.len();
+        Search {
+            betrayal,
+            chosen: Vec::with_capacity(chars.len()),
+            chars,
+            sums: [0; 4],
+            tens: 0,
+            fours: 0,
+            best: None,
+            upper,
+            open: i32::MAX,
+            penalties: TRAITS
+                .iter()
+                .map(|attr| {
+                    (0..=STARTING_SUM * n as i32)
+                        .map(|sum| betrayal.rankings.targets.average_penalty(attr, sum, n))
+                        .collect()
+                })
+                .collect(),
+            nodes: 0,
+            deadline: Instant::now() + budget,
+            stopped: false,
+            rank_only,
+            reporter: Reporter::start(progress),
+            started: Instant::now(),
+            budget,
+            root: i32::MIN,
+        }
+    }
+
+    // Report the nodes so far, the best score and how far it can be from `lower`
+    fn report(&mut self, lower: i32, done: bool) {
+        self.reporter.search(
+            &progress::Search {
+                nodes: self.nodes,
+                best: (self.upper < i32::MAX).then_some(self.upper),
+                lower,
+                elapsed: self.started.elapsed(),
+                budget: self.budget,
+            },
+            done,
+        );
+    }
+
+    fn search(&mut self, depth: usize) {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(1024) {
+            if Instant::now() > self.deadline || stopping::INTERRUPTED.load(Ordering::SeqCst) {
+                self.stopped = true;
+            }
+            self.report(self.root.min(self.upper), false);
+        }
+
+        let bound = self.bound(depth);
+        if self.stopped {
+            self.open = self.open.min(bound);
+            return;
+        }
+        if bound >= self.upper {
+            return;
+        }
+        if depth == self.chars.len() {
+            self.leaf(bound);
+            return;
+        }
+
+        for k in 0..self.chars[depth].options.len() {
+            if self.stopped {
+                self.open = self.open.min(bound);
+                return;
+            }
+            let option = self.chars[depth].options[k];
+            self.apply(depth, option, true);
+            self.search(depth + 1);
+            self.apply(depth, option, false);
+        }
+    }
+
+    fn apply(&mut self, depth: usize, option: [usize; 4], add: bool) {
+        let classes = &self.chars[depth].classes;
+        let picked: Vec<&Class> = (0..4).map(|i| &classes[i][option[i]]).collect();
+        let sign = if add { 1 } else { -1 };
+        for (i, class) in picked.iter().enumerate() {
+            self.sums[i] += sign * class.value;
+        }
+        let fours = picked.iter().filter(|x| x.index == 4).count();
+        let ten = picked.iter().map(|x| x.index).sum::<usize>() == 10;
+        if add {
+            self.fours += fours;
+            self.tens += ten as usize;
+            self.chosen.push(option);
+        } else {
+            self.fours -= fours;
+            self.tens -= ten as usize;
+            self.chosen.pop();
+        }
+    }
+
+    // Lowest fitness any completion of the characters chosen so far could have
+    fn bound(&self, depth: usize) -> i32 {
+        let rank = self.rank_bound();
+        if self.rank_only {
+            return rank;
+        }
+        let n = self.chars.len();
+        let rest = &self.chars[depth..];
+
+        // Starting values of the whole roster always add up to 15 per character, so the trait sums
+        // cannot all sit on their targets.  Fold the traits in one at a time, keeping the lowest
+        // penalty for every running total.  A track total target and card pairs are left out,
+        // which only weakens the bound.
+        let goal = (STARTING_SUM * n as i32) as usize;
+        let mut penalties = vec![i32::MAX; goal + 1];
+        penalties[0] = 0;
+        for i in 0..4 {
+            let low = self.sums[i] + rest.iter().map(|c| c.min_value[i]).sum::<i32>();
+            let high = self.sums[i] + rest.iter().map(|c| c.max_value[i]).sum::<i32>();
+            let mut next = vec![i32::MAX; goal + 1];
+            for (total, penalty) in penalties.iter().enumerate() {
+                if *penalty == i32::MAX {
+                    continue;
+                }
+                for sum in low as usize..=(high as usize).min(goal - total) {
+                    let penalty = penalty + self.penalties[i][sum];
+                    next[total + sum] = next[total + sum].min(penalty);
+                }
+            }
+            penalties = next;
+        }
+        let avg = penalties[goal];
+
+        // The open characters can land on either constitution
+        let elevens = depth - self.tens;
+        let totals_diff = (0..=rest.len())
+            .map(|x| (self.tens + x).abs_diff(elevens + rest.len() - x))
+            .min()
+            .unwrap();
+        let fours = self.fours + rest.iter().map(|c| c.max_fours).sum::<usize>();
+        let diversity = totals_diff as i32 - fours.min(n / 2) as i32;
+
+        avg + diversity + rank
+    }
+
+    // Lowest rank error any completion could have.  A character whose strength range lies wholly
+    // above another's must rank above it, and open characters can still take any strength their
+    // options reach.  The lowest rank error any order respecting that allows bounds the real one:
+    // place characters from the top, tracking which are placed.
+    fn rank_bound(&self) -> i32 {
+        let n = self.chars.len();
+        let range = |c: usize, i: usize| match self.chosen.get(c) {
+            Some(option) => {
+                let class = &self.chars[c].classes[i][option[i]];
+                (class.low, class.high)
+            }
+            None => (self.chars[c].weakest[i], self.chars[c].strongest[i]),
+        };
+        let mut rank = 0;
+        for i in 0..4 {
+            let ranges: Vec<(f32, f32)> = (0..n).map(|c| range(c, i)).collect();
+            let metric = self.betrayal.rankings.metric;
+            if n > PLACEMENT_LIMIT || metric == RankMetric::Kendall {
+                // Every forced inversion is a discordant pair, and displaces at least one rank.
+                // Too many characters to track every placed set falls back on counting them.
+                for (a, b) in (0..n).flat_map(|a| (0..n).map(move |b| (a, b))) {
+                    if self.chars[a].requested[i] < self.chars[b].requested[i]
+                        && ranges[a].1 < ranges[b].0
+                    {
+                        rank += 1;
+                    }
+                }
+                continue;
+            }
+            let above: Vec<usize> = ranges
+                .iter()
+                .map(|r| {
+                    (0..n)
+                        .filter(|d| ranges[*d].0 > r.1)
+                        .fold(0, |mask, d| mask | 1 << d)
+                })
+                .collect();
+
+            let mut placed = vec![i32::MAX; 1 << n];
+            placed[0] = 0;
+            for mask in 0..1usize << n {
+                if placed[mask] == i32::MAX {
+                    continue;
+                }
+                let position = mask.count_ones() as i32;
+                for c in (0..n).filter(|c| mask & 1 << c == 0 && above[*c] & !mask == 0) {
+                    let requested = self.chars[c].requested[i] as i32;
+                    let cost = placed[mask] + metric.place_cost(requested, position).unwrap();
+                    placed[mask | 1 << c] = placed[mask | 1 << c].min(cost);
+                }
+            }
+            rank += placed[(1 << n) - 1];
+        }
+        rank
+    }
+
+    fn leaf(&mut self, bound: i32) {
+        match self.realize() {
+            Some(ind) => {
+                let value = if self.rank_only {
+                    ind.rank_score
+                } else {
+                    ind.fitness
+                };
+                if value > bound {
+                    self.open = self.open.min(bound);
+                }
+                if value < self.upper {
+                    self.upper = value;
+                    self.best = Some(ind);
+                }
+            }
+            None => self.open = self.open.min(bound),
+        }
+    }
+
+    // Pick real tracks for the chosen classes: strengths falling in the requested order where
+    // possible, then for each character in turn, tracks that stay between its neighbours in every
+    // ranking with totals in the legal range
+    fn realize(&self) -> Option<Individual> {
+        let mut strengths: Vec<Vec<f32>> = (0..4).map(|i| self.strengths(i)).collect();
+        let mut by_rank = vec![vec![0; self.chars.len()]; 4];
+        for (c, character) in self.chars.iter().enumerate() {
+            for i in 0..4 {
+                by_rank[i][character.requested[i]] = c;
+            }
+        }
+
+        let mut chars = HashMap::new();
+        for (c, character) in self.chars.iter().enumerate() {
+            // Tracks keeping the requested order first, failing that any track of the class
+            let choices = |ordered: bool| {
+                [0, 1, 2, 3].map(|i| {
+                    let rank = character.requested[i];
+                    let above = match rank {
+                        0 => f32::INFINITY,
+                        _ => strengths[i][by_rank[i][rank - 1]],
+                    };
+                    let below = match by_rank[i].get(rank + 1) {
+                        Some(b) => strengths[i][*b],
+                        None => f32::NEG_INFINITY,
+                    };
+                    let current = strengths[i][c];
+                    let tracks = &character.classes[i][self.chosen[c][i]].tracks;
+                    let mut choices: Vec<&Choice> = tracks
+                        .iter()
+                        .filter(|x| !ordered || (below < x.0 && x.0 < above) || x.0 == current)
+                        .collect();
+                    // Only the total matters now, so keep the track nearest the picked strength for
+                    // each total
+                    choices.sort_by(|a, b| {
+                        (a.0 - current)
+                            .abs()
+                            .partial_cmp(&(b.0 - current).abs())
+                            .unwrap()
+                    });
+                    let mut by_total = BTreeMap::new();
+                    for choice in choices {
+                        by_total.entry(choice.1).or_insert(choice);
+                    }
+                    by_total.into_values().collect::<Vec<_>>()
+                })
+            };
+
+            let picked = totals(&choices(true)).or_else(|| totals(&choices(false)))?;
+            for i in 0..4 {
+                strengths[i][c] = picked[i].0;
+            }
+            let stats = TRAITS
+                .iter()
+                .zip(picked)
+                .map(|(attr, x)| (attr.to_string(), x.2.clone()))
+                .collect();
+            chars.insert(character.name.clone(), stats);
+        }
+        Some(Individual::from_chars(
+            0,
+            0,
+            0,
+            chars,
+            &self.betrayal.rankings,
+        ))
+    }
+
+    // The strength of each character in one trait.  The longest run of characters that can keep
+    // their requested order gets strengths in that order; everyone else lands as near their
+    // place in it as their class allows.
+    fn strengths(&self, i: usize) -> Vec<f32> {
+        let n = self.chars.len();
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by_key(|c| self.chars[*c].requested[i]);
+        let strengths: Vec<&[f32]> = order
+            .iter()
+            .map(|c| &self.chars[*c].classes[i][self.chosen[*c][i]].strengths[..])
+            .collect();
+
+        // chain[p][k]: longest falling chain ending with the p-th requested character at its k-th
+        // strength, and the link before it.  best[p][k]: the longest of chain[p][k..].
+        let mut chain = Vec::with_capacity(n);
+        let mut best: Vec<Vec<(usize, usize)>> = Vec::with_capacity(n);
+        for p in 0..n {
+            let links: Vec<_> = strengths[p]
+                .iter()
+                .map(|strength| {
+                    let mut link = (1, None);
+                    for q in 0..p {
+                        let above = strengths[q].partition_point(|x| x <= strength);
+                        if let Some((length, k)) = best[q].get(above) {
+                            if length + 1 > link.0 {
+                                link = (length + 1, Some((q, *k)));
+                            }
+                        }
+                    }
+                    link
+                })
+                .collect();
+            let mut suffix = vec![(0, 0); links.len()];
+            for k in (0..links.len()).rev() {
+                suffix[k] = match suffix.get(k + 1) {
+                    Some(next) if next.0 >= links[k].0 => *next,
+                    _ => (links[k].0, k),
+                };
+            }
+            chain.push(links);
+            best.push(suffix);
+        }
+
+        let mut members = Vec::new();
+        let mut link = (0..n)
+            .filter_map(|p| best[p].first().map(|x| (x.0, p, x.1)))
+            .max_by_key(|x| x.0)
+            .map(|x| (x.1, x.2));
+        while let Some((p, k)) = link {
+            members.push(p);
+            link = chain[p][k].1;
+        }
+
+        // Within the chain, each character takes a track weaker than the one before but strong
+        // enough to leave room for those after, with a total near a quarter of the legal range
+        let tracks = |p: usize| &self.chars[order[p]].classes[i][self.chosen[order[p]][i]].tracks;
+        let mut lowest = vec![f32::NEG_INFINITY; n];
+        let mut next = f32::NEG_INFINITY;
+        for p in members.iter() {
+            let above = tracks(*p).partition_point(|x| x.0 <= next);
+            if let Some(track) = tracks(*p).get(above) {
+                lowest[*p] = track.0;
+                next = track.0;
+            }
+        }
+        let target = (MIN_TOTAL + MAX_TOTAL) as f32 / 8.0;
+        let mut picked: Vec<Option<f32>> = vec![None; n];
+        let mut previous = f32::INFINITY;
+        for p in members.into_iter().rev() {
+            let tracks = tracks(p);
+            let below = &tracks[..tracks.partition_point(|x| x.0 < previous)];
+            let room = &below[below.partition_point(|x| x.0 < lowest[p])..];
+            let candidates = [room, below, tracks]
+                .into_iter()
+                .find(|x| !x.is_empty())
+                .unwrap();
+            let strength = candidates
+                .iter()
+                .min_by(|a, b| {
+                    (a.1 as f32 - target)
+                        .abs()
+                        .partial_cmp(&(b.1 as f32 - target).abs())
+                        .unwrap()
+                        .then(b.0.partial_cmp(&a.0).unwrap())
+                })
+                .unwrap()
+                .0;
+            picked[p] = Some(strength);
+            previous = strength;
+        }
+
+        // The rest go between the nearest chained characters around them, or as close as they can
+        let mut result = vec![0.0; n];
+        for p in 0..n {
+            result[order[p]] = match picked[p] {
+                Some(strength) => strength,
+                None => {
+                    let above = picked[..p].iter().rev().find_map(|x| *x);
+                    let below = picked[p + 1..].iter().find_map(|x| *x);
+                    let fits = strengths[p]
+                        .iter()
+                        .rev()
+                        .find(|x| below.is_none_or(|b| **x > b) && above.is_none_or(|a| **x < a));
+                    match (fits, above) {
+                        (Some(strength), _) => *strength,
+                        (None, Some(a)) if strengths[p][0] >= a => strengths[p][0],
+                        _ => *strengths[p].last().unwrap(),
+                    }
+                }
+            };
+        }
+        result
+    }
+}
+
+// One choice for each trait with totals in the legal range, as near the middle as possible.
+// Earlier choices win when several reach the same total.
+fn totals<'a>(choices: &[Vec<&'a Choice>; 4]) -> Option<[&'a Choice; 4]> {
+    // Reachable sums after each trait, with the choice that got there
+    let mut reachable: Vec<BTreeMap<i32, (i32, usize)>> = vec![BTreeMap::from([(0, (0, 0))])];
+    for choices in choices.iter() {
+        let mut next = BTreeMap::new();
+        for sum in reachable.last().unwrap().keys() {
+            for (k, choice) in choices.iter().enumerate() {
+                next.entry(sum + choice.1).or_insert((*sum, k));
+            }
+        }
+        reachable.push(next);
+    }
+
+    let middle = (MIN_TOTAL + MAX_TOTAL) / 2;
+    let mut sum = *reachable[4]
+        .keys()
+        .filter(|x| (MIN_TOTAL..=MAX_TOTAL).contains(*x))
+        .min_by_key(|x| ((*x - middle).abs(), **x))?;
+    let mut picked = [*choices[0].first()?; 4];
+    for i in (0..4).rev() {
+        let (previous, k) = reachable[i + 1][&sum];
+        picked[i] = choices[i][k];
+        sum = previous;
+    }
+    Some(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ga::CellularGa;
+    use crate::locks::Locks;
+    use crate::rankings::Rankings;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const RANKINGS: &str = "Might:\nAnna\nBo\nCy\n\nSpeed:\nCy\nAnna\nBo\n\n\
+        Know:\nBo\nCy\nAnna\n\nSanity:\nAnna\nCy\nBo\n";
+
+    fn betrayal() -> Betrayal {
+        Betrayal {
+            rankings: Rankings::parse(RANKINGS).unwrap(),
+            locks: Locks::default(),
+        }
+    }
+
+    // Random rosters and the local optima polishing makes of them
+    fn rosters(betrayal: &Betrayal) -> Vec<Individual> {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut rosters = CellularGa::new(30, (10, 10, 3), betrayal, betrayal, &mut rng).members;
+        for i in 0..rosters.len() {
+            let mut ind = rosters[i].clone();
+            polish::polish(&mut ind, betrayal);
+            rosters.push(ind);
+        }
+        rosters
+    }
+
+    // Follow one roster down the tree and back, checking that no bound on the way exceeds its cost
+    fn check_path(search: &mut Search, ind: &Individual, cost: i32) {
+        let n = search.chars.len();
+        let mut path = Vec::new();
+        for depth in 0..n {
+            assert!(
+                search.bound(depth) <= cost,
+                "bound above {} at depth {}",
+                cost,
+                depth
+            );
+            let c = &search.chars[depth];
+            let option: [usize; 4] = std::array::from_fn(|i| {
+                let (track, index) = ind.track(&c.name, TRAITS[i]).unwrap();
+                c.classes[i]
+                    .iter()
+                    .position(|x| x.value == track[*index] && x.index == *index)
+                    .unwrap()
+            });
+            assert!(c.options.contains(&option));
+            search.apply(depth, option, true);
+            path.push(option);
+        }
+        assert!(search.bound(n) <= cost, "bound above {} at a leaf", cost);
+        for (depth, option) in path.into_iter().enumerate().rev() {
+            search.apply(depth, option, false);
+        }
+    }
+
+    #[test]
+    fn bound_never_exceeds_fitness() {
+        let betrayal = betrayal();
+        let mut search = Search::new(
+            &betrayal,
+            i32::MAX,
+            Duration::from_secs(60),
+            false,
+            Progress::Quiet,
+        );
+        for ind in rosters(&betrayal) {
+            check_path(&mut search, &ind, ind.fitness);
+        }
+    }
+
+    #[test]
+    fn rank_bound_never_exceeds_rank_error() {
+        let betrayal = betrayal();
+        let mut search = Search::new(
+            &betrayal,
+            i32::MAX,
+            Duration::from_secs(60),
+            true,
+            Progress::Quiet,
+        );
+        for ind in rosters(&betrayal) {
+            check_path(&mut search, &ind, ind.rank_score);
+        }
+    }
+
+    #[test]
+    fn complete_search_closes_the_gap() {
+        let betrayal = betrayal();
+        let solution = solve(&betrayal, None, Duration::from_secs(600), Progress::Quiet);
+        assert!(solution.complete);
+        assert_eq!(solution.gap(), 0);
+        assert_eq!(solution.best.unwrap().fitness, solution.upper);
+        for ind in rosters(&betrayal) {
+            assert!(solution.lower <= ind.fitness);
+        }
+    }
+}
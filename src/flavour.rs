@@ -0,0 +1,256 @@
+//! The printed details of each character beyond its tracks: age, height, weight, birthday and
+//! hobbies.
+//!
+//! They play no part in the fitness, but haunts pick on the youngest or oldest explorer and the
+//! character with the next birthday goes first, so they are checked as carefully as the tracks.
+//! The flavour file is optional and uses the same blocks as the locks file:
+//!
+//! ```text
+//! Herman:
+//! Age: 42
+//! Height: 5'11"
+//! Weight: 180 lbs
+//! Birthday: March 3
+//! Hobbies: Chess, Fishing
+//! ```
+//!
+//! Any field may be left out.  The same lines follow each character's tracks in exported sheets.
+
+use std::collections::HashMap;
+use std::fs;
+
+/// Fields a flavour block may give, in the order they are written.
+pub const FIELDS: [&str; 5] = ["Age", "Height", "Weight", "Birthday", "Hobbies"];
+
+const MONTHS: [(&str, u32); 12] = [
+    ("January", 31),
+    ("February", 29),
+    ("March", 31),
+    ("April", 30),
+    ("May", 31),
+    ("June", 30),
+    ("July", 31),
+    ("August", 31),
+    ("September", 30),
+    ("October", 31),
+    ("November", 30),
+    ("December", 31),
+];
+
+/// The printed details of one character.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    pub age: Option<u32>,
+    /// Feet and inches
+    pub height: Option<(u32, u32)>,
+    /// Pounds
+    pub weight: Option<u32>,
+    /// Month from 1 and day of the month
+    pub birthday: Option<(u32, u32)>,
+    pub hobbies: Vec<String>,
+}
+
+impl Profile {
+    /// One `Field: value` line per field given, in [`FIELDS`] order.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(age) = self.age {
+            lines.push(format!("Age: {}", age));
+        }
+        if let Some((feet, inches)) = self.height {
+            lines.push(format!("Height: {}'{}\"", feet, inches));
+        }
+        if let Some(weight) = self.weight {
+            lines.push(format!("Weight: {} lbs", weight));
+        }
+        if let Some((month, day)) = self.birthday {
+            lines.push(format!(
+                "Birthday: {} {}",
+                MONTHS[month as usize - 1].0,
+                day
+            ));
+        }
+        if !self.hobbies.is_empty() {
+            lines.push(format!("Hobbies: {}", self.hobbies.join(", ")));
+        }
+        lines
+    }
+
+    /// Set one field from a `Field: value` line.  A field with no value is left out.
+    pub fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let (field, value) = line
+            .split_once(':')
+            .ok_or(format!("Expected Field: value, found {}", line))?;
+        let value = value.trim();
+        if value.is_empty() && FIELDS.contains(&field.trim()) {
+            return Ok(());
+        }
+        let bad = || format!("Bad {}: {}", field, value);
+        match field.trim() {
+            "Age" => {
+                self.age = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|x| (1..=150).contains(x))
+                        .ok_or_else(bad)?,
+                )
+            }
+            "Height" => {
+                let (feet, inches) = value
+                    .strip_suffix('"')
+                    .and_then(|x| x.split_once('\''))
+                    .ok_or_else(bad)?;
+                let feet: u32 = feet.trim().parse().map_err(|_| bad())?;
+                let inches: u32 = inches.trim().parse().map_err(|_| bad())?;
+                if !(1..=9).contains(&feet) || inches > 11 {
+                    return Err(bad());
+                }
+                self.height = Some((feet, inches));
+            }
+            "Weight" => {
+                let pounds = value.strip_suffix("lbs").unwrap_or(value).trim();
+                self.weight = Some(pounds.parse().ok().filter(|x| *x > 0).ok_or_else(bad)?);
+            }
+            "Birthday" => {
+                let (month, day) = value.split_once(' ').ok_or_else(bad)?;
+                let month = MONTHS
+                    .iter()
+                    .position(|x| x.0.eq_ignore_ascii_case(month.trim()))
+                    .ok_or_else(bad)?;
+                let day: u32 = day.trim().parse().map_err(|_| bad())?;
+                if !(1..=MONTHS[month].1).contains(&day) {
+                    return Err(bad());
+                }
+                self.birthday = Some((month as u32 + 1, day));
+            }
+            "Hobbies" => {
+                self.hobbies = value
+                    .split(',')
+                    .map(|x| x.trim().to_string())
+                    .filter(|x| !x.is_empty())
+                    .collect()
+            }
+            _ => {
+                return Err(format!(
+                    "Unknown field {}, expected {}",
+                    field,
+                    FIELDS.join(", ")
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// True when a sheet line sets a flavour field rather than a track.
+pub fn is_field(line: &str) -> bool {
+    line.split_once(':')
+        .is_some_and(|(field, _)| FIELDS.contains(&field.trim()))
+}
+
+/// The printed details of every character that has any.
+#[derive(Debug, Clone, Default)]
+pub struct Flavour {
+    profiles: HashMap<String, Profile>,
+}
+
+impl Flavour {
+    /// Read a flavour file.  A missing file means no character has any details.
+    pub fn load(file: &str, names: &[String]) -> Result<Flavour, String> {
+        match fs::read_to_string(file) {
+            Ok(buffer) => Flavour::parse(&buffer, names),
+            Err(_) => Ok(Flavour::default()),
+        }
+    }
+
+    /// Parse flavour blocks.  Every character must be one of `names` and appear once.
+    pub fn parse(buffer: &str, names: &[String]) -> Result<Flavour, String> {
+        let mut flavour = Flavour::default();
+        let mut current: Option<String> = None;
+        for line in buffer.lines().map(|x| x.trim()).filter(|x| !x.is_empty()) {
+            // An empty field such as "Hobbies:" looks like a header too
+            if let Some(name) = line.strip_suffix(':').filter(|_| !is_field(line)) {
+                if !names.iter().any(|x| x == name) {
+                    return Err(format!("{} is not in the rankings", name));
+                }
+                if flavour.profiles.contains_key(name) {
+                    return Err(format!("{} appears twice", name));
+                }
+                flavour
+                    .profiles
+                    .insert(name.to_string(), Profile::default());
+                current = Some(name.to_string());
+                continue;
+            }
+            let name = current
+                .as_ref()
+                .ok_or(format!("Expected a character name header, found {}", line))?;
+            flavour
+                .profiles
+                .get_mut(name)
+                .unwrap()
+                .parse_line(line)
+                .map_err(|e| format!("{}: {}", name, e))?;
+        }
+        Ok(flavour)
+    }
+
+    /// The details of one character, if it has any.
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.profiles.get(name)
+    }
+
+    /// Number of characters with details.
+    pub fn count(&self) -> usize {
+        self.profiles.len()
+    }
+
+    /// The flavour lines of one character, empty if it has none.
+    pub fn lines(&self, name: &str) -> Vec<String> {
+        self.get(name).map(|x| x.lines()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Herman:\nAge: 42\nHeight: 5'11\"\nWeight: 180 lbs\nBirthday: March 3\n\
+        Hobbies: Chess, Fishing\n";
+
+    fn names() -> Vec<String> {
+        vec!["Herman".to_string(), "Kyle".to_string()]
+    }
+
+    #[test]
+    fn lines_round_trip() {
+        let flavour = Flavour::parse(TEXT, &names()).unwrap();
+        let herman = flavour.get("Herman").unwrap();
+        assert_eq!(herman.age, Some(42));
+        assert_eq!(herman.height, Some((5, 11)));
+        assert_eq!(herman.birthday, Some((3, 3)));
+        assert_eq!(herman.hobbies, ["Chess", "Fishing"]);
+
+        let text = format!("Herman:\n{}\n", flavour.lines("Herman").join("\n"));
+        assert_eq!(
+            Flavour::parse(&text, &names()).unwrap().get("Herman"),
+            Some(herman)
+        );
+        assert!(flavour.lines("Kyle").is_empty());
+    }
+
+    #[test]
+    fn bad_fields_are_errors() {
+        assert!(Flavour::parse("Herman:\nBirthday: February 30\n", &names()).is_err());
+        assert!(Flavour::parse("Herman:\nShoe size: 9\n", &names()).is_err());
+        assert!(Flavour::parse("Zoe:\nAge: 30\n", &names()).is_err());
+        assert!(Flavour::parse("Herman:\nHerman:\n", &names()).is_err());
+    }
+
+    #[test]
+    fn empty_fields_are_left_out() {
+        let flavour = Flavour::parse("Herman:\nAge:\nHobbies:\n", &names()).unwrap();
+        assert_eq!(flavour.get("Herman"), Some(&Profile::default()));
+    }
+}
@@ -0,0 +1,590 @@
+//! A cellular genetic algorithm on a borderless grid, independent of what is being evolved.
+//!
+//! Members live at points on an `x_axis` by `y_axis` torus.  Each step a [`Selection`] picks a
+//! tournament from the members near some point, the two parents it names breed two children next
+//! to that point, and a [`Replacement`] decides who makes way for them.  Because members only ever
+//! compete with their neighbours, different corners of the grid are free to settle on different
+//! designs.
+
+use rand::prelude::SliceRandom;
+use rand::rngs::StdRng;
+use rand::Rng;
+
+// Selections in a row that may come back empty before a step gives up
+const SELECT_TRIES: usize = 1000;
+
+/// Something the engine can evolve.  Lower fitness is better.
+pub trait Genome: Clone {
+    /// Shared, read-only data needed to create and vary genomes.
+    type Context;
+
+    /// A new genome with the given id and grid position.  It is scored by an [`Evaluator`]
+    /// before it joins the grid.
+    fn random(id: usize, x: i32, y: i32, ctx: &Self::Context, rng: &mut StdRng) -> Self;
+
+    /// A child of two parents, including any mutation.
+    fn crossover(
+        a: &Self,
+        b: &Self,
+        id: usize,
+        x: i32,
+        y: i32,
+        ctx: &Self::Context,
+        rng: &mut StdRng,
+    ) -> Self;
+
+    /// Unique and increasing in order of creation, so smaller ids are older.
+    fn id(&self) -> usize;
+    fn position(&self) -> (i32, i32);
+    fn fitness(&self) -> i32;
+
+    /// Equal for genomes that would behave identically, whatever their id and position.
+    fn key(&self) -> String;
+}
+
+/// Scores genomes, storing the fitness inside them.
+pub trait Evaluator<G> {
+    fn evaluate(&self, genome: &mut G);
+}
+
+/// The outcome of a selection: where it happened, which two members breed, and which members
+/// competed, as indexes into [`CellularGa::members`].
+#[derive(Debug, Clone)]
+pub struct Tournament {
+    pub x: i32,
+    pub y: i32,
+    pub parents: (usize, usize),
+    pub candidates: Vec<usize>,
+}
+
+/// Chooses parents and competitors.  Returning `None` makes the engine try again elsewhere, up to
+/// a limit.
+pub trait Selection<G> {
+    fn select(&mut self, ga: &CellularGa<G>, rng: &mut StdRng) -> Option<Tournament>;
+
+    /// The configuration text that [`parse_selection`] turns back into this strategy.
+    fn describe(&self) -> String;
+}
+
+/// Puts newly bred children into the grid, removing members to make room.
+pub trait Replacement<G> {
+    fn replace(&mut self, ga: &mut CellularGa<G>, tournament: &Tournament, children: Vec<G>);
+
+    /// The configuration text that [`parse_replacement`] turns back into this strategy.
+    fn describe(&self) -> String;
+}
+
+/// Selection strategies accepted by [`parse_selection`].
+pub const SELECTIONS: &str = "tournament:K, proportional:K";
+
+/// Replacement strategies accepted by [`parse_replacement`].
+pub const REPLACEMENTS: &str = "worst, plus, steady, age";
+
+/// Build a selection strategy from configuration text such as `tournament:4`.
+pub fn parse_selection<G: Genome>(text: &str) -> Result<Box<dyn Selection<G>>, String> {
+    let (name, size) = text.split_once(':').unwrap_or((text, "4"));
+    let size: usize = match size.parse() {
+        Ok(size) if size >= 2 => size,
+        _ => return Err(format!("Tournament size must be at least 2: {}", size)),
+    };
+
+    match name {
+        "tournament" => Ok(Box::new(NeighbourhoodTournament { size })),
+        "proportional" => Ok(Box::new(FitnessProportional { size })),
+        _ => Err(format!(
+            "Unknown selection {}, expected {}",
+            name, SELECTIONS
+        )),
+    }
+}
+
+/// Build a replacement strategy from configuration text such as `plus`.
+pub fn parse_replacement<G: Genome>(text: &str) -> Result<Box<dyn Replacement<G>>, String> {
+    match text {
+        "worst" => Ok(Box::new(ReplaceWorst)),
+        "plus" => Ok(Box::new(MuPlusLambda)),
+        "steady" => Ok(Box::new(SteadyState)),
+        "age" => Ok(Box::new(ReplaceOldest)),
+        _ => Err(format!(
+            "Unknown replacement {}, expected {}",
+            text, REPLACEMENTS
+        )),
+    }
+}
+
+/// Pick `size` members at random from the window around a random point, sorted best first.  The
+/// best two become the parents.
+pub struct NeighbourhoodTournament {
+    pub size: usize,
+}
+
+impl<G: Genome> Selection<G> for NeighbourhoodTournament {
+    fn select(&mut self, ga: &CellularGa<G>, rng: &mut StdRng) -> Option<Tournament> {
+        let x = rng.gen_range(0..ga.x_axis);
+        let y = rng.gen_range(0..ga.y_axis);
+
+        let neighbours = ga.neighbours(x, y);
+        if neighbours.len() < self.size.max(2) {
+            return None;
+        }
+
+        let mut candidates: Vec<usize> = neighbours
+            .choose_multiple(rng, self.size.max(2))
+            .copied()
+            .collect();
+        candidates.sort_by_key(|i| ga.members[*i].fitness());
+
+        Some(Tournament {
+            x,
+            y,
+            parents: (candidates[0], candidates[1]),
+            candidates,
+        })
+    }
+
+    fn describe(&self) -> String {
+        format!("tournament:{}", self.size)
+    }
+}
+
+/// Pick `size` members from the window around a random point like [`NeighbourhoodTournament`],
+/// but draw the two parents at random, weighted towards better fitness.  The worst candidate gets
+/// weight 1 and each point of fitness better than it adds 1.
+pub struct FitnessProportional {
+    pub size: usize,
+}
+
+impl<G: Genome> Selection<G> for FitnessProportional {
+    fn select(&mut self, ga: &CellularGa<G>, rng: &mut StdRng) -> Option<Tournament> {
+        let mut tournament = NeighbourhoodTournament { size: self.size }.select(ga, rng)?;
+
+        let candidates = &tournament.candidates;
+        let worst = ga.members[*candidates.last().unwrap()].fitness();
+        let weights: Vec<i64> = candidates
+            .iter()
+            .map(|i| (worst - ga.members[*i].fitness()) as i64 + 1)
+            .collect();
+
+        let first = roulette(&weights, None, rng);
+        let second = roulette(&weights, Some(first), rng);
+        tournament.parents = (candidates[first], candidates[second]);
+        Some(tournament)
+    }
+
+    fn describe(&self) -> String {
+        format!("proportional:{}", self.size)
+    }
+}
+
+// Index drawn with probability proportional to its weight, never `skip`
+fn roulette(weights: &[i64], skip: Option<usize>, rng: &mut StdRng) -> usize {
+    let total: i64 = (0..weights.len())
+        .filter(|i| Some(*i) != skip)
+        .map(|i| weights[i])
+        .sum();
+    let mut pick = rng.gen_range(0..total);
+    for (i, weight) in weights.iter().enumerate() {
+        if Some(i) == skip {
+            continue;
+        }
+        if pick < *weight {
+            return i;
+        }
+        pick -= weight;
+    }
+    unreachable!()
+}
+
+/// The children replace the worst members of the tournament.
+pub struct ReplaceWorst;
+
+impl<G: Genome> Replacement<G> for ReplaceWorst {
+    fn replace(&mut self, ga: &mut CellularGa<G>, tournament: &Tournament, children: Vec<G>) {
+        let mut candidates = tournament.candidates.clone();
+        candidates.sort_by_key(|i| ga.members[*i].fitness());
+
+        let doomed: Vec<usize> = candidates
+            .iter()
+            .rev()
+            .take(children.len())
+            .map(|i| ga.members[*i].id())
+            .collect();
+        for id in doomed {
+            ga.remove(id);
+        }
+        ga.members.extend(children);
+    }
+
+    fn describe(&self) -> String {
+        "worst".to_string()
+    }
+}
+
+/// (μ+λ) replacement: the children compete with the tournament, and only the worst of the two
+/// groups together are removed.  The best member of a tournament always survives it.
+pub struct MuPlusLambda;
+
+impl<G: Genome> Replacement<G> for MuPlusLambda {
+    fn replace(&mut self, ga: &mut CellularGa<G>, tournament: &Tournament, children: Vec<G>) {
+        let mut fitnesses: Vec<i32> = tournament
+            .candidates
+            .iter()
+            .map(|i| ga.members[*i].fitness())
+            .chain(children.iter().map(|x| x.fitness()))
+            .collect();
+        fitnesses.sort();
+        let cutoff = fitnesses[tournament.candidates.len() - 1];
+
+        // Ties at the cutoff favour the members already on the grid
+        let mut room = tournament.candidates.len();
+        let mut doomed = Vec::new();
+        let mut candidates = tournament.candidates.clone();
+        candidates.sort_by_key(|i| ga.members[*i].fitness());
+        for i in candidates {
+            if room > 0 && ga.members[i].fitness() <= cutoff {
+                room -= 1;
+            } else {
+                doomed.push(ga.members[i].id());
+            }
+        }
+        let mut children = children;
+        children.sort_by_key(|x| x.fitness());
+        children.retain(|x| x.fitness() <= cutoff);
+        children.truncate(room);
+
+        for id in doomed {
+            ga.remove(id);
+        }
+        ga.members.extend(children);
+    }
+
+    fn describe(&self) -> String {
+        "plus".to_string()
+    }
+}
+
+/// Steady-state replacement: the children replace the worst members of the whole grid, wherever
+/// they are.
+pub struct SteadyState;
+
+impl<G: Genome> Replacement<G> for SteadyState {
+    fn replace(&mut self, ga: &mut CellularGa<G>, _tournament: &Tournament, children: Vec<G>) {
+        let mut order: Vec<usize> = (0..ga.members.len()).collect();
+        order.sort_by_key(|i| ga.members[*i].fitness());
+
+        let doomed: Vec<usize> = order
+            .iter()
+            .rev()
+            .take(children.len())
+            .map(|i| ga.members[*i].id())
+            .collect();
+        for id in doomed {
+            ga.remove(id);
+        }
+        ga.members.extend(children);
+    }
+
+    fn describe(&self) -> String {
+        "steady".to_string()
+    }
+}
+
+/// Age-based replacement: the children replace the oldest members of the tournament, whatever
+/// their fitness.
+pub struct ReplaceOldest;
+
+impl<G: Genome> Replacement<G> for ReplaceOldest {
+    fn replace(&mut self, ga: &mut CellularGa<G>, tournament: &Tournament, children: Vec<G>) {
+        let mut ids: Vec<usize> = tournament
+            .candidates
+            .iter()
+            .map(|i| ga.members[*i].id())
+            .collect();
+        ids.sort();
+
+        for id in ids.into_iter().take(children.len()) {
+            ga.remove(id);
+        }
+        ga.members.extend(children);
+    }
+
+    fn describe(&self) -> String {
+        "age".to_string()
+    }
+}
+
+/// The grid of members and the bookkeeping to breed more.
+pub struct CellularGa<G> {
+    pub members: Vec<G>,
+    pub x_axis: i32,
+    pub y_axis: i32,
+    /// Width of the square neighbourhood tournaments are drawn from
+    pub window: i32,
+    /// Next unused id
+    pub counter: usize,
+}
+
+impl<G: Genome> CellularGa<G> {
+    /// `size` random members scattered over the grid.
+    pub fn new(
+        size: usize,
+        (x_axis, y_axis, window): (i32, i32, i32),
+        ctx: &G::Context,
+        evaluator: &impl Evaluator<G>,
+        rng: &mut StdRng,
+    ) -> CellularGa<G> {
+        let mut members = Vec::new();
+        for id in 0..size {
+            let x = rng.gen_range(0..x_axis);
+            let y = rng.gen_range(0..y_axis);
+            let mut genome = G::random(id, x, y, ctx, rng);
+            evaluator.evaluate(&mut genome);
+            members.push(genome);
+        }
+
+        CellularGa {
+            members,
+            x_axis,
+            y_axis,
+            window,
+            counter: size,
+        }
+    }
+
+    /// Indexes of the members within half a window of `x`, `y`, wrapping around the edges.
+    pub fn neighbours(&self, x: i32, y: i32) -> Vec<usize> {
+        let h_w = self.window / 2;
+        self.members
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| {
+                let (m_x, m_y) = m.position();
+                // Allow the grid of individuals to be borderless.  The edges are connected to
+                // each other.
+                let in_x = (m_x >= x - h_w && m_x <= x + h_w)
+                    || (x + h_w > self.x_axis && m_x <= (x + h_w) % self.x_axis)
+                    || (x - h_w < 0 && m_x >= self.x_axis + x - h_w);
+                let in_y = (m_y >= y - h_w && m_y <= y + h_w)
+                    || (y + h_w > self.y_axis && m_y <= (y + h_w) % self.y_axis)
+                    || (y - h_w < 0 && m_y >= self.y_axis + y - h_w);
+                in_x && in_y
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// One generation: select, breed two children next to the tournament, replace.  Fails when
+    /// the selection finds no tournament, such as one larger than any neighbourhood.
+    pub fn step(
+        &mut self,
+        ctx: &G::Context,
+        evaluator: &impl Evaluator<G>,
+        selection: &mut dyn Selection<G>,
+        replacement: &mut dyn Replacement<G>,
+        rng: &mut StdRng,
+    ) -> Result<(), String> {
+        let tournament = (0..SELECT_TRIES)
+            .find_map(|_| selection.select(self, rng))
+            .ok_or_else(|| {
+                format!(
+                    "{} found no neighbourhood with enough members in {} tries",
+                    selection.describe(),
+                    SELECT_TRIES
+                )
+            })?;
+
+        let h_w = self.window / 2;
+        let mut children = Vec::new();
+        for _i in 0..2 {
+            let x = (tournament.x + rng.gen_range(-h_w..=h_w)).rem_euclid(self.x_axis);
+            let y = (tournament.y + rng.gen_range(-h_w..=h_w)).rem_euclid(self.y_axis);
+            let mut child = G::crossover(
+                &self.members[tournament.parents.0],
+                &self.members[tournament.parents.1],
+                self.counter,
+                x,
+                y,
+                ctx,
+                rng,
+            );
+            evaluator.evaluate(&mut child);
+            children.push(child);
+            self.counter += 1;
+        }
+
+        replacement.replace(self, &tournament, children);
+        Ok(())
+    }
+
+    /// The member with the lowest fitness.
+    pub fn best(&self) -> &G {
+        self.members.iter().min_by_key(|x| x.fitness()).unwrap()
+    }
+
+    /// Remove a member by id.
+    pub fn remove(&mut self, id: usize) -> Option<G> {
+        let index = self.members.iter().position(|x| x.id() == id)?;
+        Some(self.members.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    // Just enough of a genome to watch replacement: an id and a fitness
+    #[derive(Debug, Clone)]
+    struct Toy {
+        id: usize,
+        fitness: i32,
+    }
+
+    impl Genome for Toy {
+        type Context = ();
+
+        fn random(id: usize, _x: i32, _y: i32, _ctx: &(), rng: &mut StdRng) -> Toy {
+            Toy {
+                id,
+                fitness: rng.gen_range(0..100),
+            }
+        }
+
+        fn crossover(
+            a: &Toy,
+            _b: &Toy,
+            id: usize,
+            _x: i32,
+            _y: i32,
+            _ctx: &(),
+            _rng: &mut StdRng,
+        ) -> Toy {
+            Toy {
+                id,
+                fitness: a.fitness,
+            }
+        }
+
+        fn id(&self) -> usize {
+            self.id
+        }
+
+        fn position(&self) -> (i32, i32) {
+            (0, 0)
+        }
+
+        fn fitness(&self) -> i32 {
+            self.fitness
+        }
+
+        fn key(&self) -> String {
+            self.id.to_string()
+        }
+    }
+
+    // Toys keep the fitness they were made with
+    struct NoScore;
+
+    impl Evaluator<Toy> for NoScore {
+        fn evaluate(&self, _genome: &mut Toy) {}
+    }
+
+    // Members 0 to 5 with the given fitnesses, the first four competing, and two children
+    fn replace(strategy: &str, fitnesses: [i32; 6], children: [i32; 2]) -> Vec<usize> {
+        let mut ga = CellularGa {
+            members: (0..6)
+                .map(|id| Toy {
+                    id,
+                    fitness: fitnesses[id],
+                })
+                .collect(),
+            x_axis: 1,
+            y_axis: 1,
+            window: 1,
+            counter: 8,
+        };
+        let tournament = Tournament {
+            x: 0,
+            y: 0,
+            parents: (0, 1),
+            candidates: vec![0, 1, 2, 3],
+        };
+        let children = vec![
+            Toy {
+                id: 6,
+                fitness: children[0],
+            },
+            Toy {
+                id: 7,
+                fitness: children[1],
+            },
+        ];
+        let mut replacement = parse_replacement::<Toy>(strategy).unwrap();
+        replacement.replace(&mut ga, &tournament, children);
+        let mut ids: Vec<usize> = ga.members.iter().map(|x| x.id).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn worst_replaces_the_worst_competitors() {
+        assert_eq!(
+            replace("worst", [5, 9, 1, 7, 50, 60], [99, 99]),
+            [0, 2, 4, 5, 6, 7]
+        );
+    }
+
+    #[test]
+    fn plus_keeps_the_best_of_competitors_and_children() {
+        assert_eq!(
+            replace("plus", [5, 9, 1, 7, 50, 60], [6, 99]),
+            [0, 2, 3, 4, 5, 6]
+        );
+        assert_eq!(
+            replace("plus", [5, 9, 1, 7, 50, 60], [99, 99]),
+            [0, 1, 2, 3, 4, 5]
+        );
+    }
+
+    #[test]
+    fn steady_replaces_the_worst_anywhere() {
+        assert_eq!(
+            replace("steady", [5, 9, 1, 7, 50, 60], [99, 99]),
+            [0, 1, 2, 3, 6, 7]
+        );
+    }
+
+    #[test]
+    fn age_replaces_the_oldest_competitors() {
+        assert_eq!(
+            replace("age", [5, 9, 1, 7, 50, 60], [99, 99]),
+            [2, 3, 4, 5, 6, 7]
+        );
+    }
+
+    #[test]
+    fn strategies_describe_themselves() {
+        for text in ["worst", "plus", "steady", "age"] {
+            assert_eq!(parse_replacement::<Toy>(text).unwrap().describe(), text);
+        }
+        for text in ["tournament:4", "proportional:3"] {
+            assert_eq!(parse_selection::<Toy>(text).unwrap().describe(), text);
+        }
+        assert!(parse_selection::<Toy>("tournament:1").is_err());
+    }
+
+    #[test]
+    fn step_fails_when_no_neighbourhood_is_big_enough() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut ga: CellularGa<Toy> = CellularGa::new(6, (1, 1, 1), &(), &NoScore, &mut rng);
+        let mut replacement = ReplaceWorst;
+        let mut selection = NeighbourhoodTournament { size: 7 };
+        assert!(ga
+            .step(&(), &NoScore, &mut selection, &mut replacement, &mut rng)
+            .is_err());
+        selection.size = 6;
+        assert!(ga
+            .step(&(), &NoScore, &mut selection, &mut replacement, &mut rng)
+            .is_ok());
+    }
+}
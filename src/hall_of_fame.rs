@@ -0,0 +1,51 @@
+//! The best distinct individuals seen over a whole run.
+
+use crate::individual::Individual;
+
+/// How many individuals a new hall of fame keeps.
+pub const DEFAULT_CAPACITY: usize = 10;
+
+/// The `capacity` best individuals ever seen, best first, never two with the same genome.
+/// Individuals stay here after the population has culled them.
+#[derive(Debug, Clone)]
+pub struct HallOfFame {
+    pub capacity: usize,
+    entries: Vec<Individual>,
+}
+
+impl HallOfFame {
+    pub fn new(capacity: usize) -> HallOfFame {
+        HallOfFame {
+            capacity,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add a copy of the individual if it beats the current worst entry and its genome is not
+    /// already here.  Returns whether it was added.
+    pub fn consider(&mut self, ind: &Individual) -> bool {
+        if self.capacity == 0 {
+            return false;
+        }
+        if self.entries.len() == self.capacity
+            && ind.fitness >= self.entries.last().unwrap().fitness
+        {
+            return false;
+        }
+
+        let key = ind.genome_key();
+        if self.entries.iter().any(|x| x.genome_key() == key) {
+            return false;
+        }
+
+        let index = self.entries.partition_point(|x| x.fitness <= ind.fitness);
+        self.entries.insert(index, ind.clone());
+        self.entries.truncate(self.capacity);
+        true
+    }
+
+    /// Entries from best to worst.
+    pub fn entries(&self) -> &[Individual] {
+        &self.entries
+    }
+}
@@ -1,94 +1,193 @@
+//! One candidate roster: a full set of character sheets and its fitness.
+
+use crate::ga::Genome;
+use crate::locks::Locks;
+use crate::population::Betrayal;
+use crate::rankings::Rankings;
+use crate::rules::{self, Stats, Track, MAX_TOTAL, MIN_TOTAL, TRAITS};
 use rand::prelude::SliceRandom;
+use rand::rngs::StdRng;
+use rand::Rng;
 use std::cmp;
 use std::collections::HashMap;
 
+/// A roster of characters living at `x`, `y` on the population grid.  Lower fitness is better.
 #[derive(Debug, Clone)]
 pub struct Individual {
     pub id: usize,
     pub fitness: i32,
-    rank_score: i32,
-    avg_score: i32,
-    four_indexes: usize,
-    totals_diff: usize,
+    /// Distance of each trait's order from the requested one under the rankings' metric, summed
+    pub rank_score: i32,
+    /// Distance of the average starting values from their targets, times 10, plus the distance of
+    /// the average track total from its target if it has one
+    pub avg_score: i32,
+    /// Number of traits starting at index 4
+    pub four_indexes: usize,
+    /// Imbalance between characters with constitution 10 and those with 11
+    pub totals_diff: usize,
+    /// Imbalance and likeness of the two sides of each card, see [`crate::cards`]
+    pub pair_score: i32,
     pub x: i32,
     pub y: i32,
-    chars: HashMap<String, HashMap<String, (Vec<i32>, usize)>>,
+    chars: HashMap<String, Stats>,
 }
 
 impl Individual {
-    pub fn new(x: i32, y: i32, id: usize, rankings: &HashMap<String, Vec<String>>) -> Individual {
+    /// A fresh individual: default tracks, or the locked values, given one round of mutation.
+    /// It is not scored yet; call [`Individual::score`].
+    pub fn new(
+        x: i32,
+        y: i32,
+        id: usize,
+        rankings: &Rankings,
+        locks: &Locks,
+        rng: &mut impl Rng,
+    ) -> Individual {
         let mut chars = HashMap::new();
-        for name in rankings.get("Might").unwrap().iter() {
-            let mut stats: HashMap<String, (Vec<i32>, usize)> = HashMap::new();
-            stats.insert("Might".to_string(), (vec![2, 2, 3, 4, 5, 6, 7, 8], 2));
-            stats.insert("Speed".to_string(), (vec![2, 2, 3, 4, 5, 6, 7, 8], 3));
-            stats.insert("Know".to_string(), (vec![2, 2, 3, 4, 5, 6, 7, 8], 3));
-            stats.insert("Sanity".to_string(), (vec![2, 2, 3, 4, 5, 6, 7, 8], 3));
+        for name in rankings.names().iter() {
+            let stats = match locks.base(name) {
+                Some(stats) => stats.clone(),
+                None => Individual::default_stats(),
+            };
 
             chars.insert(name.to_string(), stats);
         }
 
         let mut ind = Individual {
-            id: id,
+            id,
             fitness: i32::MAX,
             rank_score: i32::MAX,
             avg_score: i32::MAX,
             four_indexes: 0,
             totals_diff: usize::MAX,
-            x: x,
-            y: y,
-            chars: chars,
+            pair_score: i32::MAX,
+            x,
+            y,
+            chars,
+        };
+        ind.mutate(locks, rng);
+        ind
+    }
+
+    /// Rebuild a saved individual and score it against the current rankings.
+    pub fn from_chars(
+        x: i32,
+        y: i32,
+        id: usize,
+        chars: HashMap<String, Stats>,
+        rankings: &Rankings,
+    ) -> Individual {
+        let mut ind = Individual {
+            id,
+            fitness: i32::MAX,
+            rank_score: i32::MAX,
+            avg_score: i32::MAX,
+            four_indexes: 0,
+            totals_diff: usize::MAX,
+            pair_score: i32::MAX,
+            x,
+            y,
+            chars,
         };
-        ind.mutate();
         ind.score(rankings);
         ind
     }
 
+    /// The flat starting point of every unlocked character.
+    pub fn default_stats() -> Stats {
+        let mut stats: Stats = HashMap::new();
+        stats.insert("Might".to_string(), (vec![2, 2, 3, 4, 5, 6, 7, 8], 2));
+        stats.insert("Speed".to_string(), (vec![2, 2, 3, 4, 5, 6, 7, 8], 3));
+        stats.insert("Know".to_string(), (vec![2, 2, 3, 4, 5, 6, 7, 8], 3));
+        stats.insert("Sanity".to_string(), (vec![2, 2, 3, 4, 5, 6, 7, 8], 3));
+        stats
+    }
+
+    /// A child taking each whole character from one parent or the other, then mutated.  It is
+    /// not scored yet; call [`Individual::score`].
     pub fn breed(
         parent_a: &Individual,
         parent_b: &Individual,
         x: i32,
         y: i32,
         id: usize,
-        rankings: &HashMap<String, Vec<String>>,
+        locks: &Locks,
+        rng: &mut impl Rng,
     ) -> Individual {
+        // Locked traits are identical in both parents, so crossover keeps them as they are
         let mut chars = HashMap::new();
-        for name in parent_a.chars.keys() {
-            let character = if rand::random() {
-                parent_a.chars.get(name).unwrap().clone()
+        for name in parent_a.names() {
+            let character = if rng.gen() {
+                parent_a.chars.get(&name).unwrap().clone()
             } else {
-                parent_b.chars.get(name).unwrap().clone()
+                parent_b.chars.get(&name).unwrap().clone()
             };
 
             chars.insert(name.to_string(), character);
         }
 
         let mut ind = Individual {
-            id: id,
+            id,
             fitness: i32::MAX,
             rank_score: i32::MAX,
             avg_score: i32::MAX,
             four_indexes: 0,
             totals_diff: usize::MAX,
-            x: x,
-            y: y,
-            chars: chars,
+            pair_score: i32::MAX,
+            x,
+            y,
+            chars,
         };
-        ind.mutate();
-        ind.score(rankings);
+        ind.mutate(locks, rng);
         ind
     }
 
-    fn mutate(&mut self) {
-        let mut rng = rand::thread_rng();
-        for stats in self.chars.values_mut() {
+    /// A mutated copy with a new id and position, e.g. to fill a population from a few seeds.
+    /// It is not scored yet; call [`Individual::score`].
+    pub fn mutant(
+        &self,
+        x: i32,
+        y: i32,
+        id: usize,
+        locks: &Locks,
+        rng: &mut impl Rng,
+    ) -> Individual {
+        let mut ind = Individual {
+            id,
+            x,
+            y,
+            ..self.clone()
+        };
+        ind.mutate(locks, rng);
+        ind
+    }
+
+    fn mutate(&mut self, locks: &Locks, rng: &mut impl Rng) {
+        // Walk characters and traits in a fixed order so a seeded generator replays exactly
+        for name in self.names() {
+            let stats = self.chars.get_mut(&name).unwrap();
             let mut total: i32 = stats.values().map(|x| x.0.iter().sum::<i32>()).sum();
 
-            for tup in stats.values_mut() {
+            // Index and starting value mutations work on pairs of unlocked traits
+            let free: Vec<&str> = TRAITS
+                .iter()
+                .filter(|attr| !locks.is_locked(&name, attr))
+                .copied()
+                .collect();
+            if free.is_empty() {
+                continue;
+            }
+
+            for attr in TRAITS.iter() {
+                if locks.is_locked(&name, attr) {
+                    continue;
+                }
+                let tup = stats.get_mut(*attr).unwrap();
+
                 // Mutate the list of numbers
                 for i in 0..tup.0.len() {
                     // Only mutate 10% of integers
-                    if rand::random::<f32>() > 0.1 {
+                    if rng.gen::<f32>() > 0.1 {
                         continue;
                     }
 
@@ -102,7 +201,7 @@ impl Individual {
                     // Cannot increment to more than 2 from previous number
                     let inc_2 = i == 0 || (i > 0 && tup.0[i] <= tup.0[i - 1] + 1);
                     // Total cannot be more than 155
-                    let inc_3 = total < 155;
+                    let inc_3 = total < MAX_TOTAL;
                     // Lowest number must be 1 to 4
                     let inc_4 = i > 0 || tup.0[i] < 4;
                     // Cannot be greater than 8
@@ -113,7 +212,7 @@ impl Individual {
                     // Cannot decrement to be more than 2 less than next number
                     let dec_2 = i == 7 || (i < 7 && tup.0[i] >= tup.0[i + 1] - 1);
                     // Total cannot be less than 145
-                    let dec_3 = total > 145;
+                    let dec_3 = total > MIN_TOTAL;
                     // Highest number must be 5 to 8
                     let dec_4 = i < 7 || tup.0[i] > 5;
                     // Cannot be less than 1
@@ -129,7 +228,7 @@ impl Individual {
                         possibilities.push(-1);
                     }
 
-                    let delta = possibilities.choose(&mut rng).unwrap();
+                    let delta = possibilities.choose(rng).unwrap();
                     tup.0[i] += delta;
                     total += delta;
                 }
@@ -140,9 +239,9 @@ impl Individual {
             // See which pairs of [3, 4, 5] of each could work
             // Then pick one.
             // 20% chance of happening.
-            if rand::random::<f32>() < 0.2 {
-                let attrs: Vec<String> = ["Might", "Know", "Speed", "Sanity"]
-                    .choose_multiple(&mut rng, 2)
+            if free.len() >= 2 && rng.gen::<f32>() < 0.2 {
+                let attrs: Vec<String> = free
+                    .choose_multiple(rng, 2)
                     .map(|x| x.to_string())
                     .collect();
                 let mut possibilities = Vec::new();
@@ -152,40 +251,40 @@ impl Individual {
                         if i >= 0 {
                             new_stats.get_mut(&attrs[0]).unwrap().1 += i as usize;
                         } else {
-                            new_stats.get_mut(&attrs[0]).unwrap().1 -= i.abs() as usize;
+                            new_stats.get_mut(&attrs[0]).unwrap().1 -= i.unsigned_abs() as usize;
                         }
                         if j >= 0 {
                             new_stats.get_mut(&attrs[1]).unwrap().1 += j as usize;
                         } else {
-                            new_stats.get_mut(&attrs[1]).unwrap().1 -= j.abs() as usize;
+                            new_stats.get_mut(&attrs[1]).unwrap().1 -= j.unsigned_abs() as usize;
                         }
 
-                        if Individual::valid_indexes(new_stats) {
+                        if rules::valid_indexes(&new_stats) {
                             possibilities.push((i, j));
                         }
                     }
                 }
-                let delta = possibilities.choose(&mut rng).unwrap();
+                let delta = possibilities.choose(rng).unwrap();
 
                 if delta.0 >= 0 {
                     stats.get_mut(&attrs[0]).unwrap().1 += delta.0 as usize;
                 } else {
-                    stats.get_mut(&attrs[0]).unwrap().1 -= delta.0.abs() as usize;
+                    stats.get_mut(&attrs[0]).unwrap().1 -= delta.0.unsigned_abs() as usize;
                 }
 
                 if delta.1 >= 0 {
                     stats.get_mut(&attrs[1]).unwrap().1 += delta.1 as usize;
                 } else {
-                    stats.get_mut(&attrs[1]).unwrap().1 -= delta.1.abs() as usize;
+                    stats.get_mut(&attrs[1]).unwrap().1 -= delta.1.unsigned_abs() as usize;
                 }
             }
 
             // Mutate starting values
             // Find a pair of starting values that can increment/decrement together
             // Only has a small chance of happening.  20% chance of checking for this.
-            if rand::random::<f32>() < 0.2 {
-                let attrs: Vec<String> = ["Might", "Know", "Speed", "Sanity"]
-                    .choose_multiple(&mut rng, 2)
+            if free.len() >= 2 && rng.gen::<f32>() < 0.2 {
+                let attrs: Vec<String> = free
+                    .choose_multiple(rng, 2)
                     .map(|x| x.to_string())
                     .collect();
                 let dec_tup = stats.get(&attrs[0]).unwrap();
@@ -216,80 +315,189 @@ impl Individual {
         }
     }
 
-    fn valid_indexes(stats: HashMap<String, (Vec<i32>, usize)>) -> bool {
-        // Indexes are all >= 2 and <= 4
-        if !stats.values().all(|x| x.1 >= 2 && x.1 <= 4) {
-            return false;
+    /// Every legal individual one move away, unscored: a non-starting track value up or down by
+    /// one, the starting indexes of two traits shifted together, or one starting value moved to
+    /// another trait.  These are the moves `mutate` makes, listed in a fixed order.
+    pub fn moves(&self, locks: &Locks) -> Vec<Individual> {
+        self.names()
+            .iter()
+            .flat_map(|name| self.character_moves(name, locks))
+            .collect()
+    }
+
+    /// The [`Individual::moves`] that change one character.
+    pub fn character_moves(&self, name: &str, locks: &Locks) -> Vec<Individual> {
+        let mut moves = Vec::new();
+        let stats = self.chars.get(name).unwrap();
+        let free: Vec<&str> = TRAITS
+            .iter()
+            .filter(|attr| !locks.is_locked(name, attr))
+            .copied()
+            .collect();
+        let mut candidates = Vec::new();
+
+        for attr in free.iter() {
+            let tup = stats.get(*attr).unwrap();
+            for i in (0..tup.0.len()).filter(|i| *i != tup.1) {
+                for delta in [1, -1] {
+                    let mut new_stats = stats.clone();
+                    new_stats.get_mut(*attr).unwrap().0[i] += delta;
+                    candidates.push(new_stats);
+                }
+            }
         }
 
-        // Starting values sum to 15
-        if stats.values().map(|x| x.0[x.1]).sum::<i32>() != 15 {
-            return false;
+        for (a, first) in free.iter().enumerate() {
+            for second in free[a + 1..].iter() {
+                for (i, j) in [
+                    (-1, -1),
+                    (-1, 0),
+                    (-1, 1),
+                    (0, -1),
+                    (0, 1),
+                    (1, -1),
+                    (1, 0),
+                    (1, 1),
+                ] {
+                    let mut new_stats = stats.clone();
+                    let shift = |tup: &mut Track, d: i32| tup.1 = (tup.1 as i32 + d) as usize;
+                    shift(new_stats.get_mut(*first).unwrap(), i);
+                    shift(new_stats.get_mut(*second).unwrap(), j);
+                    candidates.push(new_stats);
+                }
+            }
         }
 
-        // Constitution is 10 or 11
-        let constitution: usize = stats.values().map(|x| x.1).sum();
-        if constitution < 10 || constitution > 11 {
-            //println!("\tFail due to constitution{:?}", stats);
-            return false;
+        for dec in free.iter() {
+            for inc in free.iter().filter(|x| *x != dec) {
+                let mut new_stats = stats.clone();
+                let tup = new_stats.get_mut(*dec).unwrap();
+                tup.0[tup.1] -= 1;
+                let tup = new_stats.get_mut(*inc).unwrap();
+                tup.0[tup.1] += 1;
+                candidates.push(new_stats);
+            }
         }
 
-        // Starting values are at least either 2 or 3
-        for stat in stats.iter() {
-            match stat.0.as_str() {
-                "Might" => {
-                    if stat.1 .0[stat.1 .1] < 2 {
-                        return false;
-                    }
-                }
-                _ => {
-                    if stat.1 .0[stat.1 .1] < 3 {
-                        return false;
-                    }
-                }
+        for new_stats in candidates {
+            if rules::character_violations(name, &new_stats).is_empty() {
+                let mut ind = self.clone();
+                ind.chars.insert(name.to_string(), new_stats);
+                moves.push(ind);
             }
         }
+        moves
+    }
 
-        true
+    /// Every rule broken by this individual, one line per problem.  Mutation never produces these,
+    /// but hand edits can.
+    pub fn violations(&self) -> Vec<String> {
+        self.names()
+            .iter()
+            .flat_map(|name| rules::character_violations(name, self.chars.get(name).unwrap()))
+            .collect()
     }
 
-    fn score(&mut self, rankings: &HashMap<String, Vec<String>>) {
-        let mut rank_score = 0;
-        let mut names: Vec<String> = self.chars.keys().map(|x| x.to_string()).collect();
-        for tup in rankings.iter() {
-            // Sort in descending order based on the f32 output of the stat
-            names.sort_by(|a, b| {
-                Individual::attr_score(self, b, tup.0)
-                    .partial_cmp(&Individual::attr_score(self, a, tup.0))
-                    .unwrap()
-            });
-
-            for name in names.iter() {
-                let true_idx = tup.1.iter().position(|x| x == name).unwrap();
-                let my_idx = names.iter().position(|x| x == name).unwrap();
-                rank_score += (true_idx as i32 - my_idx as i32).abs();
+    /// Canonical text of the character tracks, equal for individuals with identical genomes.
+    pub fn genome_key(&self) -> String {
+        let mut key = String::new();
+        for name in self.names() {
+            for attr in TRAITS.iter() {
+                let tup = self.track(&name, attr).unwrap();
+                key.push_str(&format!("{:?}{};", tup.0, tup.1));
             }
         }
+        key
+    }
+
+    /// Character names in alphabetical order.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.chars.keys().map(|x| x.to_string()).collect();
+        names.sort();
+        names
+    }
+
+    /// All four tracks of one character.
+    pub fn stats(&self, name: &str) -> Option<&Stats> {
+        self.chars.get(name)
+    }
 
-        // Keep average of Might to around 3
-        // Keep average of other 3 traits to around 4
+    pub fn track(&self, name: &str, attr: &str) -> Option<&Track> {
+        self.chars.get(name)?.get(attr)
+    }
+
+    /// Change a track in place.  Call [`Individual::score`] afterwards to refresh the fitness.
+    pub fn track_mut(&mut self, name: &str, attr: &str) -> Option<&mut Track> {
+        self.chars.get_mut(name)?.get_mut(attr)
+    }
+
+    /// One line per trait with the starting value in brackets, e.g. `Might: 2 2 [3] 4 5 6 7 8`.
+    pub fn sheet(&self, name: &str) -> String {
+        let mut sheet = String::new();
+        for attr in TRAITS.iter() {
+            let tup = self.track(name, attr).unwrap();
+            let values: Vec<String> = tup
+                .0
+                .iter()
+                .enumerate()
+                .map(|(i, x)| {
+                    if i == tup.1 {
+                        format!("[{}]", x)
+                    } else {
+                        x.to_string()
+                    }
+                })
+                .collect();
+            sheet.push_str(&format!("{}: {}\n", attr, values.join(" ")));
+        }
+        sheet
+    }
+
+    /// The fitness and each of its components on one line.
+    pub fn breakdown(&self) -> String {
+        let pairs = match self.pair_score {
+            0 => String::new(),
+            score => format!(" + pairs {}", score),
+        };
+        format!(
+            "Fitness {} = rank {} + average {} + diversity {}{} (four indexes {}, totals diff {})",
+            self.fitness,
+            self.rank_score,
+            self.avg_score,
+            self.fitness - self.rank_score - self.avg_score - self.pair_score,
+            pairs,
+            self.four_indexes,
+            self.totals_diff
+        )
+    }
+
+    /// Recompute the fitness: rank error, plus distance of the starting averages from their
+    /// targets, plus a diversity term rewarding index 4 starts and balanced constitutions, plus
+    /// the card pair term when characters share cards.
+    pub fn score(&mut self, rankings: &Rankings) {
+        let rank_score = rankings
+            .iter()
+            .map(|(attr, requested)| rankings.metric.distance(requested, &self.order(attr)))
+            .sum();
+
+        // Keep the average starting value of each trait near its target, by default around 3 for
+        // Might and 4 for the rest
         let mut avg_score = 0;
-        for attr in rankings.keys() {
+        for attr in TRAITS.iter() {
             let my_sum: i32 = self
                 .chars
                 .values()
                 .map(|x| {
-                    let tup = x.get(attr).unwrap();
+                    let tup = x.get(*attr).unwrap();
                     tup.0[tup.1]
                 })
                 .sum();
-            let my_avg = my_sum as f32 / (self.chars.keys().count() as f32);
-            if attr == "Might" {
-                avg_score += ((my_avg - 3.25).abs() * 10.0) as i32;
-            } else {
-                avg_score += ((my_avg - 4.0).abs() * 10.0) as i32;
-            }
+            avg_score += rankings
+                .targets
+                .average_penalty(attr, my_sum, self.chars.len());
         }
+        let totals: i32 = self.chars.values().map(rules::total).sum();
+        avg_score += rankings.targets.total_penalty(totals, self.chars.len());
 
         // Make for more interesting diversity by:
         // 1) Increase  extremist 4 indexes, up to half the population
@@ -306,36 +514,68 @@ impl Individual {
             .count();
         let eleven_totals = self.chars.len() - ten_totals;
         let totals_diff = cmp::max(ten_totals, eleven_totals) - cmp::min(ten_totals, eleven_totals);
-        let diversity = totals_diff - cmp::min(four_indexes, self.chars.len() / 2);
+        // Signed, since a well balanced roster takes this below zero
+        let diversity = totals_diff as i32 - cmp::min(four_indexes, self.chars.len() / 2) as i32;
 
         self.avg_score = avg_score;
         self.rank_score = rank_score;
         self.totals_diff = totals_diff;
         self.four_indexes = four_indexes;
-        self.fitness = avg_score + rank_score + diversity as i32;
+        self.pair_score = rankings.pairing.score(self);
+        self.fitness = avg_score + rank_score + diversity + self.pair_score;
     }
 
-    fn attr_score(&self, name: &str, attr: &str) -> f32 {
-        let tup = self.chars.get(name).unwrap().get(attr).unwrap();
-
-        let mut weighted_sum = tup.0[tup.1] as f32;
-        let mut weight: f32 = 0.5;
-        let mut offset = 1;
-        // Keep adding offsets while there are unused numbers above or below the index
-        while offset <= tup.1 || offset + tup.1 <= 7 {
-            if offset > tup.1 {
-                // If offsetting into death, the lower number is considered 0 and not added
-                // Not possible for upper offset to be over 7
-                weighted_sum += tup.0[tup.1 + offset] as f32 * weight;
-            } else {
-                // If offsetting over the max index, reuse the max number
-                weighted_sum +=
-                    (tup.0[tup.1 - offset] + tup.0[cmp::min(tup.1 + offset, 7)]) as f32 * weight;
-            }
-            weight *= 0.5;
-            offset += 1;
-        }
+    /// Character names strongest first in one trait.  Ties keep alphabetical order so the order
+    /// does not depend on hash map ordering.
+    pub fn order(&self, attr: &str) -> Vec<String> {
+        let mut names = self.names();
+        names.sort_by(|a, b| {
+            self.attr_score(b, attr)
+                .partial_cmp(&self.attr_score(a, attr))
+                .unwrap()
+        });
+        names
+    }
+
+    /// Strength of one trait of one character: the starting value plus the values around it,
+    /// each step further away counting half as much.  Characters are ranked by this number.
+    pub fn attr_score(&self, name: &str, attr: &str) -> f32 {
+        rules::strength(self.chars.get(name).unwrap().get(attr).unwrap())
+    }
+}
+
+impl Genome for Individual {
+    type Context = Betrayal;
+
+    fn random(id: usize, x: i32, y: i32, ctx: &Betrayal, rng: &mut StdRng) -> Individual {
+        Individual::new(x, y, id, &ctx.rankings, &ctx.locks, rng)
+    }
+
+    fn crossover(
+        a: &Individual,
+        b: &Individual,
+        id: usize,
+        x: i32,
+        y: i32,
+        ctx: &Betrayal,
+        rng: &mut StdRng,
+    ) -> Individual {
+        Individual::breed(a, b, x, y, id, &ctx.locks, rng)
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn position(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    fn fitness(&self) -> i32 {
+        self.fitness
+    }
 
-        weighted_sum
+    fn key(&self) -> String {
+        self.genome_key()
     }
 }
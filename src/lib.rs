@@ -0,0 +1,40 @@
+//! Design character stat tracks for Betrayal at House on the Hill so that the characters come out
+//! in the strength order a group asked for.
+//!
+//! A [`Rankings`] file lists the requested order of characters in each trait.  A [`Population`]
+//! of [`Individual`] rosters evolves towards sheets that follow the [`rules`] and match those
+//! rankings, optionally with some traits pinned by [`Locks`].
+//!
+//! The search itself is the generic engine in [`ga`]; [`Individual`] is one [`ga::Genome`] it can
+//! evolve.
+
+pub mod cards;
+pub mod catalogue;
+pub mod checkpoint;
+pub mod conflicts;
+pub mod dashboard;
+pub mod diff;
+pub mod exact;
+pub mod flavour;
+pub mod ga;
+pub mod hall_of_fame;
+pub mod individual;
+pub mod locks;
+pub mod metric;
+pub mod niches;
+pub mod polish;
+pub mod population;
+pub mod progress;
+pub mod rankings;
+pub mod reference;
+pub mod rules;
+pub mod sensitivity;
+pub mod sheets;
+pub mod stats;
+pub mod stopping;
+pub mod trajectory;
+
+pub use individual::Individual;
+pub use locks::Locks;
+pub use population::Population;
+pub use rankings::Rankings;
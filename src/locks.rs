@@ -0,0 +1,148 @@
+//! Characters and traits pinned to approved values.
+
+use crate::catalogue::Catalogue;
+use crate::individual::Individual;
+use crate::rankings::Rankings;
+use crate::rules::{self, Stats, Track, MAX_TOTAL, MIN_TOTAL, TRAITS};
+use crate::sheets;
+use std::collections::HashMap;
+use std::fs;
+
+/// Characters or single traits pinned to fixed values.  Locked tracks are copied into every new
+/// individual and skipped by mutation, but they are still scored as part of the roster.
+///
+/// The locks file uses the character sheet blocks of [`crate::sheets`]:
+///
+/// ```text
+/// Kyle:
+/// Might: 2 3 [4] 5 5 6 7 8
+/// Speed: 2 3 3 [4] 5 6 7 8
+/// ```
+///
+/// The starting value is marked with brackets.  Traits left out of a block are optimized as usual.
+#[derive(Debug, Clone, Default)]
+pub struct Locks {
+    traits: HashMap<String, Stats>,
+    bases: HashMap<String, Stats>,
+}
+
+impl Locks {
+    /// Read a locks file.  A missing file means nothing is locked.
+    pub fn load(file: &str, rankings: &Rankings) -> Result<Locks, String> {
+        let buffer = match fs::read_to_string(file) {
+            Ok(buffer) => buffer,
+            Err(_) => return Ok(Locks::default()),
+        };
+
+        let blocks = sheets::parse_rosters(&buffer)
+            .map_err(|e| format!("Could not parse {}: {}", file, e))?;
+        let names = rankings.names();
+        let mut locks = Locks::default();
+        for (name, traits) in blocks.into_iter().flatten() {
+            if !names.contains(&name) {
+                return Err(format!("Locked character {} is not in the rankings", name));
+            }
+            if traits.is_empty() {
+                return Err(format!("No traits locked for {}", name));
+            }
+
+            let base = Locks::complete(&traits).ok_or(format!(
+                "Locked traits for {} cannot form a legal character",
+                name
+            ))?;
+            locks.bases.insert(name.clone(), base);
+            locks.traits.insert(name, traits);
+        }
+
+        Ok(locks)
+    }
+
+    // Fill in the unlocked traits with the default track, trying every starting index until the
+    // whole character satisfies the index rules.  When no default will do, any legal tracks.
+    fn complete(traits: &Stats) -> Option<Stats> {
+        let mut stats = Individual::default_stats();
+        for (attr, track) in traits.iter() {
+            stats.insert(attr.to_string(), track.clone());
+        }
+
+        let free: Vec<&str> = TRAITS
+            .iter()
+            .filter(|x| !traits.contains_key(**x))
+            .copied()
+            .collect();
+        for combo in 0..3usize.pow(free.len() as u32) {
+            let mut candidate = stats.clone();
+            for (i, attr) in free.iter().enumerate() {
+                candidate.get_mut(*attr).unwrap().1 = 2 + combo / 3usize.pow(i as u32) % 3;
+            }
+
+            let total = rules::total(&candidate);
+            if (MIN_TOTAL..=MAX_TOTAL).contains(&total) && rules::valid_indexes(&candidate) {
+                return Some(candidate);
+            }
+        }
+
+        Catalogue::pinned(traits).first_block()
+    }
+
+    /// True when the trait of this character must not change.
+    pub fn is_locked(&self, name: &str, attr: &str) -> bool {
+        self.traits
+            .get(name)
+            .is_some_and(|traits| traits.contains_key(attr))
+    }
+
+    /// The locked track of a character, if any.
+    pub fn locked(&self, name: &str, attr: &str) -> Option<&Track> {
+        self.traits.get(name)?.get(attr)
+    }
+
+    /// Legal starting stats for a character with locked traits, the rest filled with defaults.
+    pub fn base(&self, name: &str) -> Option<&Stats> {
+        self.bases.get(name)
+    }
+
+    /// The first locked trait an individual does not match, as character name and trait.
+    pub fn mismatch(&self, ind: &Individual) -> Option<(String, &'static str)> {
+        let mut names: Vec<&String> = self.traits.keys().collect();
+        names.sort();
+        for name in names {
+            for attr in TRAITS.iter() {
+                if let Some(locked) = self.locked(name, attr) {
+                    if ind.track(name, attr) != Some(locked) {
+                        return Some((name.clone(), attr));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Number of locked traits across all characters.
+    pub fn count(&self) -> usize {
+        self.traits.values().map(|x| x.len()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn traits(text: &str) -> Stats {
+        sheets::parse_rosters(text).unwrap().remove(0).remove(0).1
+    }
+
+    #[test]
+    fn completes_beyond_the_default_tracks() {
+        let locked = traits("Kyle:\nMight: 3 4 [5] 6 6 7 8 8\n");
+        let base = Locks::complete(&locked).unwrap();
+        assert_eq!(base["Might"], locked["Might"]);
+        assert!(rules::character_violations("Kyle", &base).is_empty());
+    }
+
+    #[test]
+    fn impossible_locks_are_none() {
+        let locked = traits("Kyle:\nMight: 4 5 6 7 [8] 8 8 8\n");
+        assert!(Locks::complete(&locked).is_none());
+    }
+}
@@ -1,167 +1,112 @@
-use individual::Individual;
-use rand::prelude::SliceRandom;
-use rand::Rng;
-use std::cmp;
-use std::collections::{HashMap, HashSet};
+use betrayal_characters::cards;
+use betrayal_characters::catalogue::{self, Catalogue};
+use betrayal_characters::checkpoint::{self, CHECKPOINT_FILE};
+use betrayal_characters::conflicts;
+use betrayal_characters::diff;
+use betrayal_characters::exact;
+use betrayal_characters::flavour::Flavour;
+use betrayal_characters::ga;
+use betrayal_characters::metric;
+use betrayal_characters::niches::{self, Distance, Shading};
+use betrayal_characters::polish;
+use betrayal_characters::population::Betrayal;
+use betrayal_characters::progress::{self, Progress};
+use betrayal_characters::reference;
+use betrayal_characters::rules::TRAITS;
+use betrayal_characters::sensitivity;
+use betrayal_characters::sheets;
+use betrayal_characters::stats::{self, StatsRecorder};
+use betrayal_characters::stopping::{self, StopReason, StoppingCriteria};
+use betrayal_characters::trajectory;
+use betrayal_characters::{Individual, Locks, Population, Rankings};
+use std::env;
 use std::fs;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
+use std::process;
+use std::str::FromStr;
+use std::time::Duration;
 
-mod individual;
+mod editor;
 
 const FILE: &str = "rankings.txt";
+const LOCKS_FILE: &str = "locks.txt";
+const FLAVOUR_FILE: &str = "flavour.txt";
+const REFERENCE_FILE: &str = "reference.txt";
+const USAGE: &str = "Usage: betrayal_characters [--generations N] [--resume FILE] [--autosave N]\n\
+    \x20                          [--selection STRATEGY] [--replacement STRATEGY] [--solver SOLVER]\n\
+    \x20                          [--stats FILE] [--stats-every N] [--exact SECONDS]\n\
+    \x20                          [--metric METRIC] [--seed-sheets FILE] [--seed-count N]\n\
+    \x20                          [--targets TARGETS] [--pairs PAIRS] [--progress MODE]\n\
+    \x20      betrayal_characters diff FILE FILE [--json]\n\
+    \x20      betrayal_characters reference\n\
+    \x20      betrayal_characters explain [SECONDS]\n\
+    With no arguments the interactive menu starts.  With arguments the run happens in batch mode\n\
+    and the population is written to checkpoint.txt when it ends.  diff compares the best\n\
+    individuals of two saved populations, reference scores the published characters and explain\n\
+    shows which rankings conflict and bounds the rank error, searching for up to 60 seconds.\n\
+    reference reads the published characters from reference.txt, which you copy from the cards\n\
+    as character sheets with a \"## NAME\" line before each edition.";
 
-struct Population {
-    pop: Vec<Individual>,
-    x_axis: i32,
-    y_axis: i32,
-    window: i32,
-    pop_size: usize,
-    rankings: HashMap<String, Vec<String>>,
-    counter: usize,
-}
-
-impl Population {
-    fn new(rankings: HashMap<String, Vec<String>>) -> Population {
-        let mut rng = rand::thread_rng();
-        let pop_size = 1000;
-        let mut counter = 0;
-        let x_axis = 1000;
-        let y_axis = 1000;
-
-        let mut pop = Vec::new();
-        for _i in 0..pop_size {
-            let new_x = rng.gen_range(0..x_axis);
-            let new_y = rng.gen_range(0..y_axis);
-            pop.push(Individual::new(new_x, new_y, counter, &rankings));
-            counter += 1;
-        }
-
-        Population {
-            pop: pop,
-            x_axis: x_axis,
-            y_axis: y_axis,
-            window: 100,
-            pop_size: pop_size,
-            rankings: rankings,
-            counter: counter,
-        }
-    }
-
-    fn run(&mut self, gens: usize) {
-        for i in 0..gens {
-            println!("Starting generation {} of {}", i + 1, gens);
-
-            let mut rng = &mut rand::thread_rng();
-            loop {
-                let x = rng.gen_range(0..self.x_axis);
-                let y = rng.gen_range(0..self.y_axis);
-
-                // Allow the grid of individuals to be borderless.  The edges are connected to each
-                // other.
-                let h_w = self.window / 2;
-                let mut cands: Vec<Individual> = self
-                    .pop
-                    .iter()
-                    .filter(|i| {
-                        (i.x >= x - h_w && i.x <= x + h_w)
-                            || (x + h_w > self.x_axis && i.x <= (x + h_w) % self.x_axis)
-                            || (x - h_w < 0 && i.x >= self.x_axis + x - h_w)
-                    })
-                    .filter(|i| {
-                        (i.y >= y - h_w && i.y <= y + h_w)
-                            || (y + h_w > self.y_axis && i.y <= (y + h_w) % self.y_axis)
-                            || (y - h_w < 0 && i.y >= self.y_axis + y - h_w)
-                    })
-                    .cloned()
-                    .collect();
-
-                if cands.len() < 4 {
-                    continue;
-                }
-
-                // Randomly select 4 candidates
-                cands = cands.choose_multiple(&mut rng, 4).cloned().collect();
-
-                // Sort by fitness ascending
-                cands.sort_by_key(|x| x.fitness);
-
-                // Kill the two worst solutions
-                self.cull(cands.pop().unwrap().id);
-                self.cull(cands.pop().unwrap().id);
-
-                // Breed the two best
-                for _i in 0..2 {
-                    let new_x = rng.gen_range(
-                        cmp::max(0, self.x_axis - h_w)..cmp::min(self.x_axis + h_w, self.x_axis),
-                    );
-                    let new_y = rng.gen_range(
-                        cmp::max(0, self.y_axis - h_w)..cmp::min(self.y_axis + h_w, self.y_axis),
-                    );
-                    self.pop.push(Individual::breed(
-                        &cands[0],
-                        &cands[1],
-                        new_x,
-                        new_y,
-                        self.counter,
-                        &self.rankings,
-                    ));
-                    self.counter += 1;
-                }
-                break;
-            }
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut rankings = Rankings::load(FILE).unwrap_or_else(|e| panic!("{}", e));
+    rankings.flavour =
+        Flavour::load(FLAVOUR_FILE, rankings.names()).unwrap_or_else(|e| panic!("{}", e));
+    let locks = Locks::load(LOCKS_FILE, &rankings).unwrap_or_else(|e| {
+        println!("{}", e);
+        process::exit(1)
+    });
+    // Nothing else goes to stdout, so the JSON can be piped on
+    if args.first().is_some_and(|x| x == "diff") {
+        diff_files(&args[1..], &rankings, &locks);
+        return;
+    }
+    if args.first().is_some_and(|x| x == "reference") {
+        let editions = reference::load(REFERENCE_FILE).unwrap_or_else(|e| {
+            println!("{}", e);
+            process::exit(1)
+        });
+        for edition in editions {
+            println!("{}", edition.report(&rankings.targets, None));
         }
+        return;
     }
 
-    fn cull(&mut self, id: usize) {
-        self.pop.remove(
-            self.pop
-                .iter()
-                .enumerate()
-                .find(|x| x.1.id == id)
-                .unwrap()
-                .0,
+    for attr in TRAITS.iter() {
+        println!("{:?}", rankings.get(attr).unwrap());
+    }
+    if locks.count() > 0 {
+        println!("Loaded {} locked traits from {}", locks.count(), LOCKS_FILE);
+    }
+    if rankings.flavour.count() > 0 {
+        println!(
+            "Loaded details of {} characters from {}",
+            rankings.flavour.count(),
+            FLAVOUR_FILE
         );
     }
-}
 
-fn main() {
-    let buffer =
-        fs::read_to_string(FILE).expect(format!("Could not read from FILE {}", FILE).as_str());
-
-    let mut rankings: HashMap<String, Vec<String>> = HashMap::new();
-
-    for chunk in buffer.split("\n\n").filter(|x| x.len() > 0) {
-        let mut attribute = String::new();
-        let mut ranking = Vec::new();
-        for line in chunk.split("\n").filter(|x| x.len() > 0).enumerate() {
-            if line.0 == 0 {
-                attribute = line.1.strip_suffix(":").unwrap().to_string();
-            } else {
-                ranking.push(line.1.to_string());
-            }
-        }
-        rankings.insert(attribute, ranking);
+    // Ctrl-C during a run stops it after the current generation, anywhere else it exits
+    ctrlc::set_handler(stopping::interrupt).expect("Could not set the Ctrl-C handler");
+
+    if args.first().is_some_and(|x| x == "explain") {
+        let budget = match args.get(1).map(|x| x.parse::<Seconds>()) {
+            Some(Ok(Seconds(budget))) => budget,
+            Some(Err(_)) => usage(),
+            None => Duration::from_secs(60),
+        };
+        let betrayal = Betrayal { rankings, locks };
+        let analysis = conflicts::analyse(&betrayal, None, budget, default_progress());
+        print!("{}", analysis.report(None));
+        return;
     }
-
-    // Validate 4 categories present
-    assert!(rankings.keys().count() == 4);
-    assert!(["Might", "Speed", "Know", "Sanity"]
-        .iter()
-        .all(|x| rankings.contains_key(*x)));
-
-    // Validate all characters present in each category
-    let set = HashSet::from_iter(rankings.values().nth(0).unwrap().iter());
-    for v in rankings.values() {
-        println!("{:?}", v);
+    if !args.is_empty() {
+        batch(&args, rankings, locks);
+        return;
     }
-    assert!(rankings
-        .values()
-        .map(|x| HashSet::from_iter(x.iter()))
-        .all(|x: HashSet<&String>| x == set));
 
-    let mut pop = Population::new(rankings.clone());
+    let mut pop = Population::new(rankings.clone(), locks.clone());
     loop {
-        let mut choice = String::new();
         let prompt = "\nChoose from the following options:\n\
             1) Generate new population\n\
             2) Load population from file\n\
@@ -169,39 +114,120 @@ fn main() {
             4) Save best individual to file\n\
             5) Run generations\n\
             6) Print best individual\n\
-            7) Print population\n
+            7) Print population\n\
+            8) Edit an individual\n\
+            9) Configure statistics log\n\
+            10) Configure selection and replacement\n\
+            11) Show hall of fame\n\
+            12) Polish best individual\n\
+            13) Choose solver\n\
+            14) Count legal tracks and characters\n\
+            15) Solve exactly\n\
+            16) Choose rank metric\n\
+            17) Diff two individuals\n\
+            18) Import individuals from character sheets\n\
+            19) Compare with published characters\n\
+            20) Configure card pairs\n\
+            21) Export cards of best individual\n\
+            22) Explain rank error\n\
+            23) Check sensitivity to ranking swaps\n\
+            24) Show population diversity and niches\n
             Choice: ";
 
-        print!("{}", prompt);
-
-        io::stdin()
-            .read_line(&mut choice)
-            .expect("Could not read from stdin!");
+        let choice = ask(prompt);
 
-        match choice.trim() {
-            "1" => pop = Population::new(rankings.clone()),
-            "2" => pop = load_population(&rankings),
-            "3" => save_population(&pop),
+        match choice.as_str() {
+            "1" => pop = new_population(&rankings, &locks),
+            "2" => load_population(&mut pop, &rankings, &locks),
+            "3" => save_population(&mut pop),
             "4" => save_individual(&pop),
             "5" => run_generations(&mut pop),
             "6" => print_individual(&pop),
             "7" => print_population(&pop),
+            "8" => editor::edit(&mut pop),
+            "9" => configure_stats(&mut pop),
+            "10" => configure_strategies(&mut pop),
+            "11" => hall_of_fame(&pop),
+            "12" => polish_best(&mut pop),
+            "13" => configure_solver(&mut pop),
+            "14" => count_legal(&pop),
+            "15" => {
+                let budget = ask_optional::<Seconds>("Time budget in seconds? (blank for 60) ");
+                solve_exactly(&mut pop, budget.map_or(Duration::from_secs(60), |x| x.0));
+            }
+            "16" => configure_metric(&mut pop, &mut rankings),
+            "17" => diff_individuals(&pop, &rankings, &locks),
+            "18" => import_sheets(&mut pop),
+            "19" => compare_reference(&mut pop, &mut rankings),
+            "20" => configure_pairs(&mut pop, &mut rankings),
+            "21" => export_cards(&pop),
+            "22" => {
+                let budget = ask_optional::<Seconds>("Time budget in seconds? (blank for 60) ");
+                explain_rank_error(&pop, budget.map_or(Duration::from_secs(60), |x| x.0));
+            }
+            "23" => {
+                let gens = ask_optional("Generations per swap? (blank to only polish) ");
+                let result = sensitivity::analyse(&pop, gens.unwrap_or(0));
+                print!("{}", result.report());
+            }
+            "24" => show_niches(&pop),
             _ => println!("Not a valid answer! User responded: {}", choice),
         }
     }
 }
 
-fn run_generations(pop: &mut Population) {
+// Print a prompt and read one trimmed line from stdin.  Exits when stdin is closed.
+fn ask(prompt: &str) -> String {
     let mut response = String::new();
 
-    print!("How many generations to spawn? ");
+    print!("{}", prompt);
     io::stdout().flush().unwrap();
 
-    io::stdin()
+    let read = io::stdin()
         .read_line(&mut response)
         .expect("Could not read from stdin!");
+    if read == 0 {
+        process::exit(0);
+    }
 
-    let gens: usize = match response.trim().parse() {
+    response.trim().to_string()
+}
+
+// Ask for an optional value, repeating the question until the answer is blank or parses
+fn ask_optional<T: FromStr>(prompt: &str) -> Option<T>
+where
+    T::Err: std::fmt::Display,
+{
+    loop {
+        let response = ask(prompt);
+        if response.is_empty() {
+            return None;
+        }
+        match response.parse() {
+            Ok(value) => return Some(value),
+            Err(e) => println!("Not a recognized response: {}. Error: {}", response, e),
+        }
+    }
+}
+
+// A time typed in seconds, which has to be a finite number from 0 up
+struct Seconds(Duration);
+
+impl FromStr for Seconds {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Seconds, String> {
+        let seconds: f64 = text.parse().map_err(|e| format!("{}", e))?;
+        Duration::try_from_secs_f64(seconds)
+            .map(Seconds)
+            .map_err(|_| "expected a number of seconds from 0 up".to_string())
+    }
+}
+
+fn run_generations(pop: &mut Population) {
+    let response = ask("How many generations to spawn? ");
+
+    let gens: usize = match response.parse() {
         Ok(num) => num,
         Err(e) => {
             println!("Not a recognized response: {}. Error: {}", response, e);
@@ -209,27 +235,749 @@ fn run_generations(pop: &mut Population) {
         }
     };
 
-    pop.run(gens);
+    println!("Optional stopping conditions, leave blank to skip:");
+    let criteria = StoppingCriteria {
+        generations: gens,
+        target_fitness: ask_optional("Stop at best fitness of at most? "),
+        stall_generations: ask_optional("Stop after how many generations without improvement? "),
+        time_budget: ask_optional::<Seconds>("Stop after how many seconds? ").map(|x| x.0),
+        min_diversity: ask_optional("Stop when distinct genomes fall below fraction? "),
+    };
+
+    pop.autosave =
+        ask_optional("Autosave every how many generations? (blank for never) ").unwrap_or(0);
+    let response = ask(&format!(
+        "Progress? ({}, blank to keep {}) ",
+        progress::PROGRESS,
+        pop.progress.describe()
+    ));
+    if !response.is_empty() {
+        match progress::parse_progress(&response) {
+            Ok(progress) => pop.progress = progress,
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    run(pop, &criteria);
 }
 
-fn load_population(rankings: &HashMap<String, Vec<String>>) -> Population {
-    Population::new(rankings.clone())
+fn run(pop: &mut Population, criteria: &StoppingCriteria) -> StopReason {
+    let generation = pop.generation;
+    let reason = pop.run(criteria);
+    println!(
+        "Run ended after {} generations: {}",
+        pop.generation - generation,
+        reason
+    );
+
+    if reason == StopReason::Interrupted {
+        match checkpoint::save(pop, CHECKPOINT_FILE) {
+            Ok(()) => println!("Saved checkpoint to {}", CHECKPOINT_FILE),
+            Err(e) => println!("Could not save checkpoint to {}: {}", CHECKPOINT_FILE, e),
+        }
+    }
+    reason
 }
 
-fn save_population(_pop: &Population) {
-    ()
+fn batch(args: &[String], rankings: Rankings, locks: Locks) {
+    let mut criteria = StoppingCriteria::default();
+    let mut resume = None;
+    let mut autosave = 0;
+    let mut selection = None;
+    let mut replacement = None;
+    let mut solver = None;
+    let mut stats = None;
+    let mut stats_every = 100;
+    let mut exact = None;
+    let mut rank_metric = None;
+    let mut seed_sheets = None;
+    let mut targets = None;
+    let mut pairs = None;
+    let mut seed_count = 1000;
+    let mut progress = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let value = iter.next();
+        match (arg.as_str(), value) {
+            ("--generations", Some(value)) => match value.parse() {
+                Ok(gens) => criteria.generations = gens,
+                Err(_) => usage(),
+            },
+            ("--autosave", Some(value)) => match value.parse() {
+                Ok(gens) => autosave = gens,
+                Err(_) => usage(),
+            },
+            ("--resume", Some(value)) => resume = Some(value.to_string()),
+            ("--selection", Some(value)) => selection = Some(value.to_string()),
+            ("--replacement", Some(value)) => replacement = Some(value.to_string()),
+            ("--solver", Some(value)) => solver = Some(value.to_string()),
+            ("--metric", Some(value)) => rank_metric = Some(value.to_string()),
+            ("--seed-sheets", Some(value)) => seed_sheets = Some(value.to_string()),
+            ("--targets", Some(value)) => targets = Some(value.to_string()),
+            ("--pairs", Some(value)) => pairs = Some(value.to_string()),
+            ("--seed-count", Some(value)) => match value.parse() {
+                Ok(count) => seed_count = count,
+                Err(_) => usage(),
+            },
+            ("--stats", Some(value)) => stats = Some(value.to_string()),
+            ("--stats-every", Some(value)) => match value.parse() {
+                Ok(gens) => stats_every = gens,
+                Err(_) => usage(),
+            },
+            ("--progress", Some(value)) => match progress::parse_progress(value) {
+                Ok(mode) => progress = Some(mode),
+                Err(e) => {
+                    println!("{}", e);
+                    usage()
+                }
+            },
+            ("--exact", Some(value)) => match value.parse() {
+                Ok(Seconds(budget)) => exact = Some(budget),
+                Err(_) => usage(),
+            },
+            _ => usage(),
+        }
+    }
+    if criteria.generations == 0 {
+        usage();
+    }
+
+    let mut pop = match resume {
+        Some(file) => match checkpoint::load(&file, &rankings, &locks) {
+            Ok(pop) => pop,
+            Err(e) => {
+                println!("Could not load {}: {}", file, e);
+                process::exit(1);
+            }
+        },
+        None => match seed_sheets {
+            Some(file) => {
+                let seeds = read_sheets(&file, &rankings, &locks);
+                if seeds.is_empty() {
+                    process::exit(1);
+                }
+                Population::seeded(rankings, locks, &seeds, seed_count)
+            }
+            None => Population::new(rankings, locks),
+        },
+    };
+    pop.autosave = autosave;
+    pop.progress = progress.unwrap_or_else(default_progress);
+    if let Some(selection) = selection {
+        pop.selection = ga::parse_selection(&selection).unwrap_or_else(|e| {
+            println!("{}", e);
+            usage()
+        });
+    }
+    if let Some(replacement) = replacement {
+        pop.replacement = ga::parse_replacement(&replacement).unwrap_or_else(|e| {
+            println!("{}", e);
+            usage()
+        });
+    }
+    if let Some(solver) = solver {
+        pop.solver = trajectory::parse_solver(&solver).unwrap_or_else(|e| {
+            println!("{}", e);
+            usage()
+        });
+    }
+
+    if let Some(text) = targets {
+        match reference::parse_targets(&text, REFERENCE_FILE) {
+            Ok(targets) => pop.set_targets(targets),
+            Err(e) => {
+                println!("{}", e);
+                usage()
+            }
+        }
+    }
+    if let Some(text) = pairs {
+        match cards::parse_pairing(&text, pop.betrayal.rankings.names()) {
+            Ok(pairing) => pop.set_pairing(pairing),
+            Err(e) => {
+                println!("{}", e);
+                usage()
+            }
+        }
+    }
+    if let Some(text) = rank_metric {
+        match metric::parse_metric(&text) {
+            Ok(metric) => pop.set_metric(metric),
+            Err(e) => {
+                println!("{}", e);
+                usage()
+            }
+        }
+    }
+
+    if let Some(file) = stats {
+        match StatsRecorder::create(&file, stats_every) {
+            Ok(stats) => pop.stats = Some(stats),
+            Err(e) => {
+                println!("Could not create {}: {}", file, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    // Interrupted runs already wrote their checkpoint
+    let mut reason = run(&mut pop, &criteria);
+    if let StopReason::Failed(_) = reason {
+        process::exit(1);
+    }
+    if let (Some(budget), false) = (exact, reason == StopReason::Interrupted) {
+        reason = solve_exactly(&mut pop, budget);
+    }
+    println!("{}", metric::report(pop.best(), &pop.betrayal.rankings));
+    if reason != StopReason::Interrupted {
+        if let Err(e) = checkpoint::save(&mut pop, CHECKPOINT_FILE) {
+            println!("Could not save checkpoint to {}: {}", CHECKPOINT_FILE, e);
+            process::exit(1);
+        }
+        println!("Saved population to {}", CHECKPOINT_FILE);
+    }
+}
+
+// Search for the optimum from the population's best, report how close that best was and inject
+// anything better
+fn solve_exactly(pop: &mut Population, budget: Duration) -> StopReason {
+    let incumbent = pop.best().fitness;
+    let solution = exact::solve(&pop.betrayal, Some(pop.best()), budget, pop.progress);
+    println!(
+        "Searched {} nodes{}",
+        solution.nodes,
+        if solution.complete {
+            ""
+        } else {
+            " before running out of time"
+        }
+    );
+    if solution.gap() == 0 {
+        println!("Best fitness {} is optimal", solution.upper);
+    } else {
+        println!(
+            "Best fitness {}, no roster can score below {} (gap {})",
+            solution.upper,
+            solution.lower,
+            solution.gap()
+        );
+    }
+    println!(
+        "Population best fitness {} is {} above the lower bound",
+        incumbent,
+        incumbent - solution.lower
+    );
+
+    if let Some(best) = solution.best {
+        println!("{}", best.breakdown());
+        let worst = pop.inject(best);
+        println!(
+            "Injected individual {} in place of individual {}",
+            pop.grid.counter - 1,
+            worst
+        );
+    }
+
+    if solution.interrupted {
+        StopReason::Interrupted
+    } else {
+        StopReason::Completed
+    }
+}
+
+// A bar redrawn in place makes a mess of logs, so those get a line now and then
+fn default_progress() -> Progress {
+    match io::stdout().is_terminal() {
+        true => Progress::Bar,
+        false => Progress::Seconds(10.0),
+    }
 }
 
-fn save_individual(_pop: &Population) {
-    ()
+fn usage() -> ! {
+    println!("{}", USAGE);
+    process::exit(2);
+}
+
+fn configure_stats(pop: &mut Population) {
+    let file = ask("Statistics CSV file? (blank to stop logging) ");
+    if file.is_empty() {
+        pop.stats = None;
+        return;
+    }
+
+    let response = ask("Record every how many generations? ");
+    let interval: usize = match response.parse() {
+        Ok(num) => num,
+        Err(e) => {
+            println!("Not a recognized response: {}. Error: {}", response, e);
+            return;
+        }
+    };
+
+    match StatsRecorder::create(&file, interval) {
+        Ok(stats) => pop.stats = Some(stats),
+        Err(e) => println!("Could not create {}: {}", file, e),
+    }
+}
+
+fn configure_strategies(pop: &mut Population) {
+    println!(
+        "Currently selection {} and replacement {}",
+        pop.selection.describe(),
+        pop.replacement.describe()
+    );
+
+    let response = ask(&format!("Selection? ({}, blank to keep) ", ga::SELECTIONS));
+    if !response.is_empty() {
+        match ga::parse_selection(&response) {
+            Ok(selection) => pop.selection = selection,
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    let response = ask(&format!(
+        "Replacement? ({}, blank to keep) ",
+        ga::REPLACEMENTS
+    ));
+    if !response.is_empty() {
+        match ga::parse_replacement(&response) {
+            Ok(replacement) => pop.replacement = replacement,
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+fn configure_solver(pop: &mut Population) {
+    println!("Currently {}", pop.solver.describe());
+
+    let response = ask(&format!(
+        "Solver? ({}, blank to keep) ",
+        trajectory::SOLVERS
+    ));
+    if !response.is_empty() {
+        match trajectory::parse_solver(&response) {
+            Ok(solver) => pop.solver = solver,
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+// Compare the best individuals of two saved populations, as a table or as JSON
+fn diff_files(args: &[String], rankings: &Rankings, locks: &Locks) {
+    let (files, flags): (Vec<&String>, Vec<&String>) =
+        args.iter().partition(|x| !x.starts_with("--"));
+    let json = match flags.as_slice() {
+        [] => false,
+        [flag] if *flag == "--json" => true,
+        _ => usage(),
+    };
+    if files.len() != 2 {
+        usage();
+    }
+
+    let best: Vec<Individual> = files
+        .iter()
+        .map(|file| match checkpoint::load(file, rankings, locks) {
+            Ok(pop) => pop.best().clone(),
+            Err(e) => {
+                println!("Could not load {}: {}", file, e);
+                process::exit(1);
+            }
+        })
+        .collect();
+    match diff::diff(&best[0], &best[1]) {
+        Ok(diff) if json => print!("{}", diff.json()),
+        Ok(diff) => print!("{}", diff.table(io::stdout().is_terminal())),
+        Err(e) => {
+            println!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn diff_individuals(pop: &Population, rankings: &Rankings, locks: &Locks) {
+    let prompt = "individual? (id, checkpoint file for its best, blank for the population best) ";
+    let Some(before) = pick_individual(pop, rankings, locks, &format!("First {}", prompt)) else {
+        return;
+    };
+    let Some(after) = pick_individual(pop, rankings, locks, &format!("Second {}", prompt)) else {
+        return;
+    };
+    match diff::diff(&before, &after) {
+        Ok(diff) => {
+            print!("{}", diff.table(io::stdout().is_terminal()));
+            let file = ask("Export JSON to file? (blank to skip) ");
+            if !file.is_empty() {
+                match fs::write(&file, diff.json()) {
+                    Ok(()) => println!("Saved diff to {}", file),
+                    Err(e) => println!("Could not save to {}: {}", file, e),
+                }
+            }
+        }
+        Err(e) => println!("{}", e),
+    }
+}
+
+// An individual from the grid or the hall of fame by id, or the best of a saved population
+fn pick_individual(
+    pop: &Population,
+    rankings: &Rankings,
+    locks: &Locks,
+    prompt: &str,
+) -> Option<Individual> {
+    let response = ask(prompt);
+    if response.is_empty() {
+        return Some(pop.best().clone());
+    }
+    if let Ok(id) = response.parse::<usize>() {
+        let found = pop
+            .grid
+            .members
+            .iter()
+            .chain(pop.hall_of_fame.entries())
+            .find(|x| x.id == id);
+        if found.is_none() {
+            println!("No individual {} in the population or hall of fame", id);
+        }
+        return found.cloned();
+    }
+    match checkpoint::load(&response, rankings, locks) {
+        Ok(loaded) => Some(loaded.best().clone()),
+        Err(e) => {
+            println!("Could not load {}: {}", response, e);
+            None
+        }
+    }
+}
+
+// Score the published characters against the population's targets, next to the population best,
+// and optionally aim for an edition's averages
+fn compare_reference(pop: &mut Population, rankings: &mut Rankings) {
+    let editions = match reference::load(REFERENCE_FILE) {
+        Ok(editions) => editions,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    for (i, edition) in editions.iter().enumerate() {
+        print!("{}) ", i + 1);
+        println!(
+            "{}",
+            edition.report(&pop.betrayal.rankings.targets, Some(pop.best()))
+        );
+    }
+
+    let response = ask(&format!(
+        "Targets? ({}, blank to keep) ",
+        reference::TARGETS
+    ));
+    if response.is_empty() {
+        return;
+    }
+    match reference::parse_targets(&response, REFERENCE_FILE) {
+        Ok(targets) => {
+            rankings.targets = targets;
+            pop.set_targets(targets);
+            println!(
+                "Aiming for {}, best fitness now {}",
+                targets.describe(),
+                pop.best().fitness
+            );
+        }
+        Err(e) => println!("{}", e),
+    }
+}
+
+// Choose which characters share cards.  New populations keep the choice.
+fn configure_pairs(pop: &mut Population, rankings: &mut Rankings) {
+    println!("Currently {}", pop.betrayal.rankings.pairing.describe());
+
+    let response = ask(&format!(
+        "Card pairs? ({}, blank to keep) ",
+        cards::PAIRINGS
+    ));
+    if response.is_empty() {
+        return;
+    }
+    match cards::parse_pairing(&response, rankings.names()) {
+        Ok(pairing) => {
+            rankings.pairing = pairing.clone();
+            pop.set_pairing(pairing);
+            println!("{}", pop.best().breakdown());
+        }
+        Err(e) => println!("{}", e),
+    }
+}
+
+fn export_cards(pop: &Population) {
+    let best = pop.best();
+    let text = cards::render(best, &pop.betrayal.rankings);
+    print!("{}", text);
+
+    let file = ask("Export to file? (blank to skip) ");
+    if file.is_empty() {
+        return;
+    }
+    match fs::write(&file, text) {
+        Ok(()) => println!("Saved the cards of individual {} to {}", best.id, file),
+        Err(e) => println!("Could not save to {}: {}", file, e),
+    }
+}
+
+// Show which requested orders the rules rule out and how much of the best's rank error is forced
+fn explain_rank_error(pop: &Population, budget: Duration) {
+    let analysis = conflicts::analyse(&pop.betrayal, Some(pop.best()), budget, pop.progress);
+    print!("{}", analysis.report(Some(pop.best())));
+}
+
+// Rescore the population under another rank metric.  New populations keep using it.
+fn configure_metric(pop: &mut Population, rankings: &mut Rankings) {
+    println!("Currently {}", pop.betrayal.rankings.metric.describe());
+
+    let response = ask(&format!(
+        "Rank metric? ({}, blank to keep) ",
+        metric::METRICS
+    ));
+    if response.is_empty() {
+        return;
+    }
+    match metric::parse_metric(&response) {
+        Ok(metric) => {
+            rankings.metric = metric;
+            pop.set_metric(metric);
+            println!("{}", metric::report(pop.best(), &pop.betrayal.rankings));
+        }
+        Err(e) => println!("{}", e),
+    }
+}
+
+fn count_legal(pop: &Population) {
+    println!("{} legal tracks", catalogue::tracks().len());
+    let catalogue = Catalogue::new();
+    for attr in TRAITS.iter() {
+        println!(
+            "{} legal {} tracks with starting positions",
+            catalogue.starts(attr).len(),
+            attr
+        );
+    }
+    let (ten, eleven) = catalogue.count_blocks();
+    println!(
+        "{} legal characters: {} with constitution 10, {} with 11",
+        ten + eleven,
+        ten,
+        eleven
+    );
+
+    let locks = &pop.betrayal.locks;
+    for name in pop.betrayal.rankings.names() {
+        if TRAITS.iter().any(|attr| locks.is_locked(name, attr)) {
+            let (ten, eleven) = Catalogue::for_character(name, locks).count_blocks();
+            println!("{} legal sheets for {} with its locks", ten + eleven, name);
+        }
+    }
+}
+
+fn ask_file() -> String {
+    let file = ask(&format!("File name? (blank for {}) ", CHECKPOINT_FILE));
+    if file.is_empty() {
+        CHECKPOINT_FILE.to_string()
+    } else {
+        file
+    }
+}
+
+fn load_population(pop: &mut Population, rankings: &Rankings, locks: &Locks) {
+    let file = ask_file();
+    match checkpoint::load(&file, rankings, locks) {
+        Ok(loaded) => {
+            println!(
+                "Loaded {} individuals at generation {} from {}",
+                loaded.grid.members.len(),
+                loaded.generation,
+                file
+            );
+            *pop = loaded;
+        }
+        Err(e) => println!("Could not load {}: {}", file, e),
+    }
+}
+
+fn save_population(pop: &mut Population) {
+    let file = ask_file();
+    match checkpoint::save(pop, &file) {
+        Ok(()) => println!("Saved {} individuals to {}", pop.grid.members.len(), file),
+        Err(e) => println!("Could not save to {}: {}", file, e),
+    }
+}
+
+fn save_individual(pop: &Population) {
+    let file = ask("File name? ");
+    let best = pop.best();
+
+    let text = format!(
+        "# Individual {}, fitness {}\n\n{}",
+        best.id,
+        best.fitness,
+        sheets::write(best, &pop.betrayal.rankings.flavour)
+    );
+    match fs::write(&file, text) {
+        Ok(()) => println!("Saved individual {} to {}", best.id, file),
+        Err(e) => println!("Could not save to {}: {}", file, e),
+    }
+}
+
+// Read hand-written or exported sheets and put each roster in place of the current worst
+fn import_sheets(pop: &mut Population) {
+    let file = ask("File name? ");
+    for ind in read_sheets(&file, &pop.betrayal.rankings, &pop.betrayal.locks) {
+        println!("{}", ind.breakdown());
+        let worst = pop.inject(ind);
+        println!(
+            "Injected individual {} in place of individual {}",
+            pop.grid.counter - 1,
+            worst
+        );
+    }
+}
+
+// The rosters in a sheets file that match the locks, reporting the rest
+fn read_sheets(file: &str, rankings: &Rankings, locks: &Locks) -> Vec<Individual> {
+    let rosters = match sheets::load(file, rankings) {
+        Ok(rosters) => rosters,
+        Err(e) => {
+            println!("{}", e);
+            return Vec::new();
+        }
+    };
+    let mut matching = Vec::new();
+    for (i, ind) in rosters.into_iter().enumerate() {
+        match locks.mismatch(&ind) {
+            Some((name, attr)) => println!(
+                "Skipped roster {}: does not match the locked {} {}",
+                i + 1,
+                name,
+                attr
+            ),
+            None => matching.push(ind),
+        }
+    }
+    matching
+}
+
+// A fresh population, optionally grown partly or wholly from character sheets
+fn new_population(rankings: &Rankings, locks: &Locks) -> Population {
+    let file = ask("Seed from character sheets? (file, blank for none) ");
+    if file.is_empty() {
+        return Population::new(rankings.clone(), locks.clone());
+    }
+    let seeds = read_sheets(&file, rankings, locks);
+    let count = ask_optional("How many individuals from the sheets? (blank for all 1000) ");
+    let pop = Population::seeded(
+        rankings.clone(),
+        locks.clone(),
+        &seeds,
+        count.unwrap_or(1000),
+    );
+    println!(
+        "Seeded from {} rosters, best fitness {}",
+        seeds.len(),
+        pop.best().fitness
+    );
+    pop
+}
+
+fn hall_of_fame(pop: &Population) {
+    let entries = pop.hall_of_fame.entries();
+    for (i, ind) in entries.iter().enumerate() {
+        println!("{}) Individual {}", i + 1, ind.id);
+        println!("{}", ind.breakdown());
+    }
+
+    let file = ask("Export to file? (blank to skip) ");
+    if file.is_empty() {
+        return;
+    }
+    let mut text = String::new();
+    for ind in entries.iter() {
+        text.push_str(&format!(
+            "# Individual {}, fitness {}\n\n",
+            ind.id, ind.fitness
+        ));
+        text.push_str(&sheets::write(ind, &pop.betrayal.rankings.flavour));
+    }
+    match fs::write(&file, text) {
+        Ok(()) => println!("Saved {} individuals to {}", entries.len(), file),
+        Err(e) => println!("Could not save to {}: {}", file, e),
+    }
+}
+
+// Polish a copy of the best individual and put it back if it improved
+fn polish_best(pop: &mut Population) {
+    let mut ind = pop.best().clone();
+    let id = ind.id;
+    let polished = polish::polish(&mut ind, &pop.betrayal);
+    println!(
+        "Polished individual {} from fitness {} to {} in {} moves",
+        id, polished.before, polished.after, polished.steps
+    );
+    println!("{}", ind.breakdown());
+
+    if polished.gain() > 0 {
+        let worst = pop.inject(ind);
+        println!(
+            "Injected individual {} in place of individual {}",
+            pop.grid.counter - 1,
+            worst
+        );
+    }
 }
 
 fn print_individual(pop: &Population) {
-    println!("{:?}", pop.pop.iter().min_by_key(|x| x.fitness));
+    println!("{:?}", pop.best());
+    println!("{}", metric::report(pop.best(), &pop.betrayal.rankings));
+}
+
+// Diversity of the population under every distance, then the grid shaded as asked
+fn show_niches(pop: &Population) {
+    let members = &pop.grid.members;
+    println!(
+        "{} members, {:.1}% distinct genomes",
+        members.len(),
+        stats::distinct_genomes(members) * 100.0
+    );
+    for distance in [Distance::Tracks, Distance::Values, Distance::Starts] {
+        println!(
+            "Mean pairwise distance in {}: {:.2}",
+            distance.describe(),
+            niches::mean_distance(members, distance)
+        );
+    }
+
+    let response = ask(&format!(
+        "Shade by fitness, or cluster by distance? (fitness, {}, blank for fitness) ",
+        niches::DISTANCES
+    ));
+    let shading = match response.as_str() {
+        "" | "fitness" => Shading::Fitness,
+        text => match niches::parse_distance(text) {
+            Ok(distance) => Shading::Cluster(distance),
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        },
+    };
+    print!(
+        "{}",
+        niches::heatmap(&pop.grid, shading, (60, 20), io::stdout().is_terminal())
+    );
 }
 
 fn print_population(pop: &Population) {
-    for i in pop.pop.iter() {
+    for i in pop.grid.members.iter() {
         println!("{:?}", i);
     }
 }
@@ -0,0 +1,202 @@
+//! Ways of measuring how far a roster's order of characters is from the requested one.
+//!
+//! Each metric is a distance: 0 when every character sits where the group asked, growing as the
+//! order drifts.  The footrule, the sum of how far each character is from its requested place, is
+//! the original rank score.  The others weigh the same mistakes differently: Kendall counts pairs
+//! in the wrong order, so one character far out of place costs as much as many small slips; rho
+//! squares each displacement, so large misses dominate; and the top-k footrule counts mistakes
+//! among the strongest characters several times over.
+
+use crate::individual::Individual;
+use crate::rankings::Rankings;
+use crate::rules::TRAITS;
+
+/// Metrics accepted by [`parse_metric`].
+pub const METRICS: &str = "footrule, kendall, rho, top[:K]";
+
+/// Deepest top-k weighting [`parse_metric`] accepts, far beyond any roster, so the weights stay
+/// small enough to add up.
+pub const MAX_TOP_DEPTH: usize = 100;
+
+/// How the rank part of the fitness compares an order to the requested one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RankMetric {
+    /// Sum of absolute displacements (Spearman's footrule)
+    #[default]
+    Footrule,
+    /// Number of pairs in the opposite order (Kendall tau distance)
+    Kendall,
+    /// Sum of squared displacements, the distance behind Spearman's rho
+    Rho,
+    /// Footrule where the character requested at place `p` counts `max(k - p, 1)` times
+    TopWeighted(usize),
+}
+
+impl RankMetric {
+    /// Configuration text that [`parse_metric`] turns back into this metric.
+    pub fn describe(&self) -> String {
+        match self {
+            RankMetric::Footrule => "footrule".to_string(),
+            RankMetric::Kendall => "kendall".to_string(),
+            RankMetric::Rho => "rho".to_string(),
+            RankMetric::TopWeighted(k) => format!("top:{}", k),
+        }
+    }
+
+    /// Distance of `actual` from `requested`, both strongest first over the same names.
+    pub fn distance(&self, requested: &[String], actual: &[String]) -> i32 {
+        let places: Vec<(i32, i32)> = requested
+            .iter()
+            .enumerate()
+            .map(|(place, name)| {
+                let actual = actual.iter().position(|x| x == name).unwrap();
+                (place as i32, actual as i32)
+            })
+            .collect();
+        if *self != RankMetric::Kendall {
+            return places
+                .iter()
+                .map(|(place, actual)| self.place_cost(*place, *actual).unwrap())
+                .sum();
+        }
+
+        let mut discordant = 0;
+        for (i, a) in places.iter().enumerate() {
+            discordant += places[i + 1..].iter().filter(|b| a.1 > b.1).count() as i32;
+        }
+        discordant
+    }
+
+    /// What one character requested at `place` costs when it lands at `actual`, for the metrics
+    /// that add up one character at a time.  Kendall counts pairs instead, so it has none.
+    pub fn place_cost(&self, place: i32, actual: i32) -> Option<i32> {
+        let d = (actual - place).abs();
+        match self {
+            RankMetric::Footrule => Some(d),
+            RankMetric::Kendall => None,
+            RankMetric::Rho => Some(d * d),
+            RankMetric::TopWeighted(k) => Some((*k as i32 - place).max(1) * d),
+        }
+    }
+}
+
+/// Build a metric from configuration text such as `kendall` or `top:5`.
+pub fn parse_metric(text: &str) -> Result<RankMetric, String> {
+    let mut parts = text.split(':');
+    match parts.next().unwrap() {
+        "footrule" => Ok(RankMetric::Footrule),
+        "kendall" => Ok(RankMetric::Kendall),
+        "rho" => Ok(RankMetric::Rho),
+        "top" => {
+            let k = parts.next().unwrap_or("3");
+            match k.parse() {
+                Ok(k) if (1..=MAX_TOP_DEPTH).contains(&k) => Ok(RankMetric::TopWeighted(k)),
+                _ => Err(format!(
+                    "Bad top-k depth: {}, expected 1 to {}",
+                    k, MAX_TOP_DEPTH
+                )),
+            }
+        }
+        name => Err(format!(
+            "Unknown rank metric {}, expected {}",
+            name, METRICS
+        )),
+    }
+}
+
+/// Every metric for every trait of one individual, with Kendall's tau and Spearman's rho as
+/// correlations from -1 (reversed) to 1 (exactly as requested).
+pub fn report(ind: &Individual, rankings: &Rankings) -> String {
+    let n = rankings.names().len() as f64;
+    let k = match rankings.metric {
+        RankMetric::TopWeighted(k) => k,
+        _ => 3,
+    };
+    let mut text = format!(
+        "{:<8} {:>8} {:>8} {:>6} {:>8} {:>6} {:>8}\n",
+        "Trait",
+        "footrule",
+        "kendall",
+        "tau",
+        "squares",
+        "rho",
+        format!("top:{}", k)
+    );
+    let mut sums = [0; 4];
+    for attr in TRAITS.iter() {
+        let requested = rankings.get(attr).unwrap();
+        let actual = ind.order(attr);
+        let distances = [
+            RankMetric::Footrule,
+            RankMetric::Kendall,
+            RankMetric::Rho,
+            RankMetric::TopWeighted(k),
+        ]
+        .map(|metric| metric.distance(requested, &actual));
+        for (sum, distance) in sums.iter_mut().zip(distances) {
+            *sum += distance;
+        }
+
+        // With a single character both correlations are trivially perfect
+        let tau = 1.0 - 4.0 * distances[1] as f64 / (n * (n - 1.0)).max(1.0);
+        let rho = 1.0 - 6.0 * distances[2] as f64 / (n * (n * n - 1.0)).max(1.0);
+        text.push_str(&format!(
+            "{:<8} {:>8} {:>8} {:>6.2} {:>8} {:>6.2} {:>8}\n",
+            attr, distances[0], distances[1], tau, distances[2], rho, distances[3]
+        ));
+    }
+    text.push_str(&format!(
+        "{:<8} {:>8} {:>8} {:>6} {:>8} {:>6} {:>8}\n",
+        "Total", sums[0], sums[1], "", sums[2], "", sums[3]
+    ));
+    text.push_str(&format!(
+        "Scoring uses {}: rank score {}",
+        rankings.metric.describe(),
+        ind.rank_score
+    ));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(text: &str) -> Vec<String> {
+        text.split(' ').map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn distances_of_two_swaps() {
+        let requested = names("a b c d");
+        let actual = names("b a d c");
+        assert_eq!(RankMetric::Footrule.distance(&requested, &actual), 4);
+        assert_eq!(RankMetric::Kendall.distance(&requested, &actual), 2);
+        assert_eq!(RankMetric::Rho.distance(&requested, &actual), 4);
+        // a counts twice at the top
+        assert_eq!(RankMetric::TopWeighted(2).distance(&requested, &actual), 5);
+        for metric in [RankMetric::Footrule, RankMetric::Kendall, RankMetric::Rho] {
+            assert_eq!(metric.distance(&requested, &requested), 0);
+        }
+    }
+
+    #[test]
+    fn large_misses_weigh_more_under_rho() {
+        let requested = names("a b c d");
+        let actual = names("d b c a");
+        assert_eq!(RankMetric::Footrule.distance(&requested, &actual), 6);
+        assert_eq!(RankMetric::Kendall.distance(&requested, &actual), 5);
+        assert_eq!(RankMetric::Rho.distance(&requested, &actual), 18);
+    }
+
+    #[test]
+    fn parse_round_trips() {
+        for text in ["footrule", "kendall", "rho", "top:5"] {
+            assert_eq!(parse_metric(text).unwrap().describe(), text);
+        }
+        assert_eq!(parse_metric("top").unwrap(), RankMetric::TopWeighted(3));
+        assert!(parse_metric("top:0").is_err());
+        assert!(parse_metric("top:101").is_err());
+        assert!(parse_metric("top:18446744073709551615").is_err());
+        assert!(parse_metric("spearman").is_err());
+    }
+}
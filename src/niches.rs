@@ -0,0 +1,280 @@
+//! How far apart the members of a population are, and where on the grid they sit.
+//!
+//! Members only compete with their neighbours on the torus, so distinct designs can hold out in
+//! different corners of it.  A [`Distance`] measures how different two genomes are, the mean
+//! pairwise distance sums that up for a whole population, and [`heatmap`] draws the grid, shrunk
+//! to fit a terminal, shaded by fitness or lettered by genome cluster.
+
+use crate::ga::CellularGa;
+use crate::individual::Individual;
+use crate::rules::TRAITS;
+
+/// Distances accepted by [`parse_distance`].
+pub const DISTANCES: &str = "tracks, values, starts";
+
+/// Most clusters [`heatmap`] letters, one colour each.
+pub const CLUSTERS: usize = 6;
+
+const RESET: &str = "\x1b[0m";
+const PALETTE: [&str; CLUSTERS] = [
+    "\x1b[31m", "\x1b[32m", "\x1b[33m", "\x1b[34m", "\x1b[35m", "\x1b[36m",
+];
+// Best to worst fitness
+const SHADES: [char; 5] = ['@', '#', '+', ':', '.'];
+const HEAT: [&str; 5] = ["\x1b[32m", "\x1b[32m", "\x1b[33m", "\x1b[31m", "\x1b[31m"];
+
+/// How the difference between two genomes is counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Distance {
+    /// Tracks that differ at all, over every character and trait
+    Tracks,
+    /// Summed absolute difference of the track values, plus one for each moved starting index
+    #[default]
+    Values,
+    /// Summed absolute difference of the starting values, plus one for each moved starting index
+    Starts,
+}
+
+impl Distance {
+    /// Configuration text that [`parse_distance`] turns back into this distance.
+    pub fn describe(&self) -> String {
+        match self {
+            Distance::Tracks => "tracks",
+            Distance::Values => "values",
+            Distance::Starts => "starts",
+        }
+        .to_string()
+    }
+
+    /// The distance between two individuals with the same characters.
+    pub fn between(&self, a: &Individual, b: &Individual) -> u32 {
+        self.flat(&Flat::of(a), &Flat::of(b))
+    }
+
+    fn flat(&self, a: &Flat, b: &Flat) -> u32 {
+        let moved = a
+            .indexes
+            .iter()
+            .zip(b.indexes.iter())
+            .filter(|(x, y)| x != y)
+            .count() as u32;
+        match self {
+            Distance::Tracks => a
+                .tracks()
+                .zip(b.tracks())
+                .filter(|((x, i), (y, j))| x != y || i != j)
+                .count() as u32,
+            Distance::Values => {
+                a.values
+                    .iter()
+                    .zip(b.values.iter())
+                    .map(|(x, y)| x.abs_diff(*y))
+                    .sum::<u32>()
+                    + moved
+            }
+            Distance::Starts => {
+                a.starts()
+                    .zip(b.starts())
+                    .map(|(x, y)| x.abs_diff(y))
+                    .sum::<u32>()
+                    + moved
+            }
+        }
+    }
+}
+
+// Every track of an individual laid end to end, characters in name order and traits in
+// TRAITS order, so that distances need no lookups
+struct Flat {
+    values: Vec<i32>,
+    indexes: Vec<usize>,
+}
+
+impl Flat {
+    fn of(ind: &Individual) -> Flat {
+        let mut flat = Flat {
+            values: Vec::new(),
+            indexes: Vec::new(),
+        };
+        for name in ind.names() {
+            for attr in TRAITS.iter() {
+                let track = ind.track(&name, attr).unwrap();
+                flat.values.extend(track.0.iter());
+                flat.indexes.push(track.1);
+            }
+        }
+        flat
+    }
+
+    fn tracks(&self) -> impl Iterator<Item = (&[i32], usize)> {
+        let length = self.values.len() / self.indexes.len().max(1);
+        self.values
+            .chunks(length.max(1))
+            .zip(self.indexes.iter().copied())
+    }
+
+    fn starts(&self) -> impl Iterator<Item = i32> + '_ {
+        self.tracks().map(|(track, index)| track[index])
+    }
+}
+
+/// Build a distance from configuration text.
+pub fn parse_distance(text: &str) -> Result<Distance, String> {
+    match text {
+        "tracks" => Ok(Distance::Tracks),
+        "values" => Ok(Distance::Values),
+        "starts" => Ok(Distance::Starts),
+        _ => Err(format!("Unknown distance {}, expected {}", text, DISTANCES)),
+    }
+}
+
+/// Average distance over every pair of members, 0 for fewer than two.
+pub fn mean_distance(members: &[Individual], distance: Distance) -> f64 {
+    let n = members.len();
+    if n < 2 {
+        return 0.0;
+    }
+    let flat: Vec<Flat> = members.iter().map(Flat::of).collect();
+    let mut total = 0u64;
+    for (i, a) in flat.iter().enumerate() {
+        for b in flat[i + 1..].iter() {
+            total += distance.flat(a, b) as u64;
+        }
+    }
+    total as f64 / (n * (n - 1) / 2) as f64
+}
+
+/// Up to `k` clusters of members: the best member founds the first, then the member farthest from
+/// every founder founds the next, and everyone joins the nearest founder.  Returns each member's
+/// cluster, numbered in founding order, and the founders' indexes.
+pub fn clusters(members: &[Individual], distance: Distance, k: usize) -> (Vec<usize>, Vec<usize>) {
+    let Some(first) = (0..members.len()).min_by_key(|i| members[*i].fitness) else {
+        return (Vec::new(), Vec::new());
+    };
+    let flat: Vec<Flat> = members.iter().map(Flat::of).collect();
+    let mut founders = vec![first];
+    let mut nearest: Vec<(u32, usize)> = flat
+        .iter()
+        .map(|x| (distance.flat(x, &flat[first]), 0))
+        .collect();
+    while founders.len() < k {
+        let (far, _) = nearest
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, x)| (x.0, usize::MAX - i))
+            .unwrap();
+        if nearest[far].0 == 0 {
+            break;
+        }
+        let cluster = founders.len();
+        founders.push(far);
+        for (i, x) in flat.iter().enumerate() {
+            let d = distance.flat(x, &flat[far]);
+            if d < nearest[i].0 {
+                nearest[i] = (d, cluster);
+            }
+        }
+    }
+    (nearest.into_iter().map(|x| x.1).collect(), founders)
+}
+
+/// What the cells of a [`heatmap`] show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shading {
+    /// The best fitness in the cell, by fifths of the population's range
+    Fitness,
+    /// The cluster of the best member in the cell
+    Cluster(Distance),
+}
+
+/// The grid shrunk to `width` by `height` cells, one character each and blank where nobody
+/// lives, with a legend below.  With `color`, cells are painted with ANSI codes as well.
+pub fn heatmap(
+    grid: &CellularGa<Individual>,
+    shading: Shading,
+    (width, height): (usize, usize),
+    color: bool,
+) -> String {
+    let members = &grid.members;
+    // Index of the best member in each cell
+    let mut cells: Vec<Option<usize>> = vec![None; width * height];
+    for (i, ind) in members.iter().enumerate() {
+        let column = ind.x as usize * width / grid.x_axis as usize;
+        let row = ind.y as usize * height / grid.y_axis as usize;
+        let cell = &mut cells[row * width + column];
+        if cell.is_none_or(|j| ind.fitness < members[j].fitness) {
+            *cell = Some(i);
+        }
+    }
+
+    let best = members.iter().map(|x| x.fitness).min().unwrap_or(0);
+    let worst = members.iter().map(|x| x.fitness).max().unwrap_or(0);
+    let shade = |fitness: i32| {
+        let span = (worst - best).max(1) as usize;
+        ((fitness - best) as usize * SHADES.len() / (span + 1)).min(SHADES.len() - 1)
+    };
+    let (assigned, founders) = match shading {
+        Shading::Cluster(distance) => clusters(members, distance, CLUSTERS),
+        Shading::Fitness => (Vec::new(), Vec::new()),
+    };
+    let paint = |symbol: char, code: &str| {
+        if color {
+            format!("{}{}{}", code, symbol, RESET)
+        } else {
+            symbol.to_string()
+        }
+    };
+    let symbol = |i: usize| match shading {
+        Shading::Fitness => {
+            let s = shade(members[i].fitness);
+            paint(SHADES[s], HEAT[s])
+        }
+        Shading::Cluster(_) => {
+            let c = assigned[i];
+            paint((b'A' + c as u8) as char, PALETTE[c])
+        }
+    };
+
+    let border = format!("+{}+\n", "-".repeat(width));
+    let mut text = border.clone();
+    for row in cells.chunks(width) {
+        text.push('|');
+        for cell in row {
+            match cell {
+                Some(i) => text.push_str(&symbol(*i)),
+                None => text.push(' '),
+            }
+        }
+        text.push_str("|\n");
+    }
+    text.push_str(&border);
+
+    match shading {
+        Shading::Fitness => {
+            let span = (worst - best).max(1) as usize;
+            for (s, symbol) in SHADES.iter().enumerate() {
+                let low = best + (s * (span + 1)).div_ceil(SHADES.len()) as i32;
+                text.push_str(&format!(
+                    "{} fitness from {}  ",
+                    paint(*symbol, HEAT[s]),
+                    low
+                ));
+            }
+            text.push('\n');
+        }
+        Shading::Cluster(distance) => {
+            for (c, founder) in founders.iter().enumerate() {
+                let size = assigned.iter().filter(|x| **x == c).count();
+                text.push_str(&format!(
+                    "{} {} members, founded by individual {} (fitness {}), {} from A\n",
+                    paint((b'A' + c as u8) as char, PALETTE[c]),
+                    size,
+                    members[*founder].id,
+                    members[*founder].fitness,
+                    distance.between(&members[*founder], &members[founders[0]])
+                ));
+            }
+        }
+    }
+    text
+}
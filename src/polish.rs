@@ -0,0 +1,57 @@
+//! Steepest-descent polishing of a single individual.
+//!
+//! The GA tends to finish a move or two short of a local optimum, e.g. with two characters one
+//! adjacent swap away from their requested order.  Polishing tries every single legal move from
+//! [`Individual::moves`], takes the one that lowers fitness the most and repeats until none does.
+//! It uses no randomness, so the same individual always polishes to the same result.
+
+use crate::individual::Individual;
+use crate::population::Betrayal;
+use crate::stopping;
+
+/// What polishing achieved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Polished {
+    pub before: i32,
+    pub after: i32,
+    /// Moves taken to reach the local optimum
+    pub steps: usize,
+}
+
+impl Polished {
+    /// How much fitness dropped.
+    pub fn gain(&self) -> i32 {
+        self.before - self.after
+    }
+}
+
+/// Polish an individual in place until no single move improves it.  Ties go to the first move
+/// found.  Inside a run, Ctrl-C stops polishing after the current move.
+pub fn polish(ind: &mut Individual, betrayal: &Betrayal) -> Polished {
+    ind.score(&betrayal.rankings);
+    let before = ind.fitness;
+    let mut steps = 0;
+
+    while !stopping::interrupted() {
+        let mut best: Option<Individual> = None;
+        for mut candidate in ind.moves(&betrayal.locks) {
+            candidate.score(&betrayal.rankings);
+            if candidate.fitness < best.as_ref().map_or(ind.fitness, |x| x.fitness) {
+                best = Some(candidate);
+            }
+        }
+        match best {
+            Some(better) => {
+                *ind = better;
+                steps += 1;
+            }
+            None => break,
+        }
+    }
+
+    Polished {
+        before,
+        after: ind.fitness,
+        steps,
+    }
+}
@@ -0,0 +1,350 @@
+//! The Betrayal character search on top of the generic engine in [`crate::ga`].
+
+use crate::cards::Pairing;
+use crate::checkpoint::{self, CHECKPOINT_FILE};
+use crate::dashboard::Key;
+use crate::ga::{
+    CellularGa, Evaluator, NeighbourhoodTournament, ReplaceWorst, Replacement, Selection,
+};
+use crate::hall_of_fame::{self, HallOfFame};
+use crate::individual::Individual;
+use crate::locks::Locks;
+use crate::metric::RankMetric;
+use crate::progress::{Frame, Progress, Reporter};
+use crate::rankings::Rankings;
+use crate::rules::Targets;
+use crate::stats::StatsRecorder;
+use crate::stopping::{self, StopReason, StoppingCriteria};
+use crate::trajectory::{Solver, Trajectory};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::slice;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// What the characters are being designed against: the requested rankings and any locked traits.
+/// It is both the [`crate::ga::Genome::Context`] of an [`Individual`] and its [`Evaluator`].
+#[derive(Debug, Clone)]
+pub struct Betrayal {
+    pub rankings: Rankings,
+    pub locks: Locks,
+}
+
+impl Evaluator<Individual> for Betrayal {
+    fn evaluate(&self, ind: &mut Individual) {
+        ind.score(&self.rankings);
+    }
+}
+
+/// Individuals spread over a borderless grid.  By default each generation picks a random point,
+/// holds a tournament of four individuals near it, kills the worst two and breeds the best two;
+/// `selection` and `replacement` swap in other strategies from [`crate::ga`].
+pub struct Population {
+    pub grid: CellularGa<Individual>,
+    pub betrayal: Betrayal,
+    /// Generations run over the lifetime of this population, across runs and resumes
+    pub generation: usize,
+    /// Time spent running, across runs and resumes
+    pub elapsed: Duration,
+    /// Optional CSV log written as generations run
+    pub stats: Option<StatsRecorder>,
+    /// Write a checkpoint every this many generations, 0 for never
+    pub autosave: usize,
+    /// How much runs print while they go
+    pub progress: Progress,
+    /// Best distinct individuals seen, including ones since culled
+    pub hall_of_fame: HallOfFame,
+    pub selection: Box<dyn Selection<Individual>>,
+    pub replacement: Box<dyn Replacement<Individual>>,
+    /// The genetic algorithm over the grid, or a single-trajectory search from its best
+    pub solver: Solver,
+    pub(crate) rng: StdRng,
+}
+
+impl Population {
+    /// A population of 1000 fresh individuals scattered over a 1000 by 1000 grid.
+    pub fn new(rankings: Rankings, locks: Locks) -> Population {
+        let mut rng = StdRng::from_entropy();
+        let betrayal = Betrayal { rankings, locks };
+        let grid = CellularGa::new(1000, (1000, 1000, 100), &betrayal, &betrayal, &mut rng);
+        Population::from_grid(grid, betrayal, rng)
+    }
+
+    /// A population of 1000 like [`Population::new`], but with `count` of them grown from
+    /// `seeds`: each seed once as it is, then mutated copies of the seeds in turn.  The seeds must
+    /// rank the same characters and match the locks.
+    pub fn seeded(
+        rankings: Rankings,
+        locks: Locks,
+        seeds: &[Individual],
+        count: usize,
+    ) -> Population {
+        let mut rng = StdRng::from_entropy();
+        let betrayal = Betrayal { rankings, locks };
+        let count = if seeds.is_empty() { 0 } else { count.min(1000) };
+        let mut grid = CellularGa::new(
+            1000 - count,
+            (1000, 1000, 100),
+            &betrayal,
+            &betrayal,
+            &mut rng,
+        );
+
+        for (i, seed) in seeds.iter().cycle().take(count).enumerate() {
+            let x = rng.gen_range(0..grid.x_axis);
+            let y = rng.gen_range(0..grid.y_axis);
+            let mut ind = if i < seeds.len() {
+                let mut ind = seed.clone();
+                (ind.x, ind.y, ind.id) = (x, y, grid.counter);
+                ind
+            } else {
+                seed.mutant(x, y, grid.counter, &betrayal.locks, &mut rng)
+            };
+            ind.score(&betrayal.rankings);
+            grid.members.push(ind);
+            grid.counter += 1;
+        }
+        Population::from_grid(grid, betrayal, rng)
+    }
+
+    /// Wrap an existing grid, e.g. one loaded from a file, with the default strategies.
+    pub fn from_grid(grid: CellularGa<Individual>, betrayal: Betrayal, rng: StdRng) -> Population {
+        let mut hall_of_fame = HallOfFame::new(hall_of_fame::DEFAULT_CAPACITY);
+        for ind in grid.members.iter() {
+            hall_of_fame.consider(ind);
+        }
+
+        Population {
+            grid,
+            betrayal,
+            generation: 0,
+            elapsed: Duration::ZERO,
+            stats: None,
+            autosave: 0,
+            progress: Progress::default(),
+            hall_of_fame,
+            selection: Box::new(NeighbourhoodTournament { size: 4 }),
+            replacement: Box::new(ReplaceWorst),
+            solver: Solver::Genetic,
+            rng,
+        }
+    }
+
+    /// Run generations until one of the stopping criteria is met.  With a single-trajectory
+    /// [`Solver`] each generation is one step of the search, and the best individual it finds
+    /// replaces the worst on the grid at the end.
+    pub fn run(&mut self, criteria: &StoppingCriteria) -> StopReason {
+        let start = Instant::now();
+        // Time spent paused on the dashboard, which counts against nothing
+        let mut paused = Duration::ZERO;
+        let gens = criteria.generations;
+        let mut best = self.best().fitness;
+        let mut stalled = 0;
+        let mut reason = StopReason::Completed;
+        let mut trajectory = match self.solver {
+            Solver::Genetic => None,
+            _ => Some(Trajectory::new(self.best().clone())),
+        };
+        let mut reporter = Reporter::start(self.progress);
+        let outer = stopping::start();
+        for i in 0..gens {
+            let stepped = match (&self.solver, trajectory.as_mut()) {
+                (Solver::Annealing(annealing), Some(trajectory)) => {
+                    annealing.step(trajectory, i, gens, &self.betrayal, &mut self.rng);
+                    Ok(())
+                }
+                (Solver::Tabu(tabu), Some(trajectory)) => {
+                    tabu.step(trajectory, &self.betrayal);
+                    Ok(())
+                }
+                _ => self.evolve(),
+            };
+            if let Err(e) = stepped {
+                reason = StopReason::Failed(e);
+                break;
+            }
+            // The search's best so far joins the hall of fame under a fresh id
+            if let Some(trajectory) = trajectory.as_mut() {
+                if trajectory.best.fitness < best {
+                    trajectory.best.id = self.grid.counter;
+                    self.grid.counter += 1;
+                    self.hall_of_fame.consider(&trajectory.best);
+                }
+            }
+            // Keys act before the stopping check, so a stop takes effect straight away
+            if let Some(dashboard) = reporter.dashboard.as_mut() {
+                let mut pause: Option<Instant> = None;
+                loop {
+                    dashboard.draw(&Frame {
+                        generation: i + 1,
+                        generations: gens,
+                        best: trajectory.as_ref().map_or(self.best(), |x| &x.best),
+                        members: &self.grid.members,
+                        elapsed: start.elapsed()
+                            - paused
+                            - pause.map_or(Duration::ZERO, |x| x.elapsed()),
+                        time_budget: criteria.time_budget,
+                    });
+                    match dashboard.poll() {
+                        Some(Key::Pause) => dashboard.paused = !dashboard.paused,
+                        Some(Key::Save) => {
+                            dashboard.note = match checkpoint::save(self, CHECKPOINT_FILE) {
+                                Ok(()) => format!("Saved checkpoint to {}", CHECKPOINT_FILE),
+                                Err(e) => format!("Could not save to {}: {}", CHECKPOINT_FILE, e),
+                            }
+                        }
+                        Some(Key::Stop) => {
+                            stopping::INTERRUPTED.store(true, Ordering::SeqCst);
+                            dashboard.paused = false;
+                        }
+                        // Ctrl-C still stops a paused run
+                        None if dashboard.paused
+                            && !stopping::INTERRUPTED.load(Ordering::SeqCst) =>
+                        {
+                            pause.get_or_insert_with(Instant::now);
+                            thread::sleep(Duration::from_millis(50));
+                        }
+                        None => break,
+                    }
+                }
+                paused += pause.map_or(Duration::ZERO, |x| x.elapsed());
+            } else {
+                reporter.update(&Frame {
+                    generation: i + 1,
+                    generations: gens,
+                    best: trajectory.as_ref().map_or(self.best(), |x| &x.best),
+                    members: &self.grid.members,
+                    elapsed: start.elapsed() - paused,
+                    time_budget: criteria.time_budget,
+                });
+            }
+            let members = match trajectory.as_ref() {
+                Some(trajectory) => slice::from_ref(&trajectory.current),
+                None => &self.grid.members[..],
+            };
+
+            self.generation += 1;
+            if let Some(stats) = self.stats.as_mut() {
+                let elapsed = self.elapsed + start.elapsed() - paused;
+                if let Err(e) = stats.record(self.generation, elapsed, members) {
+                    println!("Could not write statistics to {}: {}", stats.file, e);
+                    self.stats = None;
+                }
+            }
+
+            let new_best = match trajectory.as_ref() {
+                Some(trajectory) => trajectory.best.fitness,
+                None => self.best().fitness,
+            };
+            if new_best < best {
+                best = new_best;
+                stalled = 0;
+            } else {
+                stalled += 1;
+            }
+            if let Some(stop) = criteria.check(i + 1, stalled, start.elapsed() - paused, members) {
+                reason = stop;
+            }
+            // Trajectory finds reach the checkpoint through the hall of fame
+            if self.autosave > 0 && self.generation.is_multiple_of(self.autosave) {
+                if let Err(e) = checkpoint::save(self, CHECKPOINT_FILE) {
+                    println!("Could not autosave to {}: {}", CHECKPOINT_FILE, e);
+                }
+            }
+            if reason != StopReason::Completed {
+                break;
+            }
+        }
+        stopping::finish(outer);
+        reporter.finish();
+        if let Some(trajectory) = trajectory {
+            if trajectory.best.fitness < self.best().fitness {
+                self.inject(trajectory.best);
+            }
+        }
+        self.elapsed += start.elapsed() - paused;
+        reason
+    }
+
+    // One generation of the genetic algorithm
+    fn evolve(&mut self) -> Result<(), String> {
+        let first_child = self.grid.counter;
+        self.grid.step(
+            &self.betrayal,
+            &self.betrayal,
+            self.selection.as_mut(),
+            self.replacement.as_mut(),
+            &mut self.rng,
+        )?;
+        // Children that made it onto the grid are always at the end
+        for ind in self.grid.members.iter().rev() {
+            if ind.id < first_child {
+                break;
+            }
+            self.hall_of_fame.consider(ind);
+        }
+        Ok(())
+    }
+
+    /// Switch the rank metric and rescore everyone under it, the hall of fame included.
+    pub fn set_metric(&mut self, metric: RankMetric) {
+        self.betrayal.rankings.metric = metric;
+        self.rescore();
+    }
+
+    /// Switch what the averages aim for and rescore everyone, the hall of fame included.
+    pub fn set_targets(&mut self, targets: Targets) {
+        self.betrayal.rankings.targets = targets;
+        self.rescore();
+    }
+
+    /// Switch which characters share cards and rescore everyone, the hall of fame included.
+    pub fn set_pairing(&mut self, pairing: Pairing) {
+        self.betrayal.rankings.pairing = pairing;
+        self.rescore();
+    }
+
+    fn rescore(&mut self) {
+        for ind in self.grid.members.iter_mut() {
+            ind.score(&self.betrayal.rankings);
+        }
+
+        let mut hall_of_fame = HallOfFame::new(self.hall_of_fame.capacity);
+        for ind in self
+            .hall_of_fame
+            .entries()
+            .iter()
+            .chain(self.grid.members.iter())
+        {
+            let mut ind = ind.clone();
+            ind.score(&self.betrayal.rankings);
+            hall_of_fame.consider(&ind);
+        }
+        self.hall_of_fame = hall_of_fame;
+    }
+
+    /// The individual with the lowest fitness.
+    pub fn best(&self) -> &Individual {
+        self.grid.best()
+    }
+
+    /// Put an outside individual, such as a hand edit, in place of the current worst.  The
+    /// individual gets a fresh id; returns the id of the individual it replaced.
+    pub fn inject(&mut self, mut ind: Individual) -> usize {
+        let worst = self
+            .grid
+            .members
+            .iter()
+            .max_by_key(|x| x.fitness)
+            .unwrap()
+            .id;
+        self.grid.remove(worst);
+
+        ind.id = self.grid.counter;
+        self.grid.counter += 1;
+        self.hall_of_fame.consider(&ind);
+        self.grid.members.push(ind);
+        worst
+    }
+}
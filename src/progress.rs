@@ -0,0 +1,302 @@
+//! What a run prints while it goes.
+//!
+//! Printing a line for every generation floods the terminal and slows the run down, so a
+//! [`Progress`] picks how much to show: nothing, a summary line every so many generations or
+//! seconds, a single progress bar redrawn in place, a line per generation, or the live
+//! [`Dashboard`].  Every generational solver goes through
+//! [`crate::population::Population::run`], and the exact search, which has no generations,
+//! reports its nodes through the same [`Reporter`], so the same setting covers them all.
+
+use crate::dashboard::Dashboard;
+use crate::individual::Individual;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Progress settings accepted by [`parse_progress`].
+pub const PROGRESS: &str = "quiet, every:N, every:Ns, bar, verbose, dashboard";
+
+// Least time between redraws of the bar
+const BAR_REFRESH: Duration = Duration::from_millis(100);
+const BAR_WIDTH: usize = 30;
+
+/// How much a run prints.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Progress {
+    /// Nothing until the run ends
+    Quiet,
+    /// A summary line every so many generations, or thousand nodes of the exact search, and at
+    /// the end
+    Generations(usize),
+    /// A summary line every so many seconds, and at the end
+    Seconds(f64),
+    /// One line redrawn in place with the rate and time left
+    #[default]
+    Bar,
+    /// A summary line every generation
+    Verbose,
+    /// The live [`Dashboard`], or summaries every 5 seconds where it cannot start
+    Dashboard,
+}
+
+impl Progress {
+    /// Configuration text that [`parse_progress`] turns back into this setting.
+    pub fn describe(&self) -> String {
+        match self {
+            Progress::Quiet => "quiet".to_string(),
+            Progress::Generations(gens) => format!("every:{}", gens),
+            Progress::Seconds(seconds) => format!("every:{}s", seconds),
+            Progress::Bar => "bar".to_string(),
+            Progress::Verbose => "verbose".to_string(),
+            Progress::Dashboard => "dashboard".to_string(),
+        }
+    }
+}
+
+/// Build a progress setting from configuration text such as `bar` or `every:10s`.
+pub fn parse_progress(text: &str) -> Result<Progress, String> {
+    match text {
+        "quiet" => return Ok(Progress::Quiet),
+        "bar" => return Ok(Progress::Bar),
+        "verbose" => return Ok(Progress::Verbose),
+        "dashboard" => return Ok(Progress::Dashboard),
+        _ => (),
+    }
+    let every = text
+        .strip_prefix("every:")
+        .ok_or(format!("Unknown progress {}, expected {}", text, PROGRESS))?;
+    let bad = || format!("Bad interval: {}", every);
+    match every.strip_suffix('s') {
+        Some(seconds) => match seconds.parse::<f64>() {
+            Ok(seconds) if seconds > 0.0 => Ok(Progress::Seconds(seconds)),
+            _ => Err(bad()),
+        },
+        None => match every.parse::<usize>() {
+            Ok(gens) if gens > 0 => Ok(Progress::Generations(gens)),
+            _ => Err(bad()),
+        },
+    }
+}
+
+/// Where a run stands after one generation.
+pub struct Frame<'a> {
+    /// Generations done in this run, counting from 1
+    pub generation: usize,
+    pub generations: usize,
+    pub best: &'a Individual,
+    pub members: &'a [Individual],
+    /// Time spent in this run
+    pub elapsed: Duration,
+    pub time_budget: Option<Duration>,
+}
+
+impl Frame<'_> {
+    /// Mean fitness of the members.
+    pub fn mean(&self) -> f64 {
+        self.members.iter().map(|x| x.fitness as f64).sum::<f64>()
+            / self.members.len().max(1) as f64
+    }
+
+    /// Generations per second so far.
+    pub fn rate(&self) -> f64 {
+        self.generation as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+
+    /// Time left at the current rate, or until the time budget runs out if that comes first.
+    pub fn eta(&self) -> Option<Duration> {
+        let by_generations = (self.generation > 0).then(|| {
+            let left = self.generations.saturating_sub(self.generation) as u32;
+            self.elapsed / self.generation as u32 * left
+        });
+        let by_budget = self
+            .time_budget
+            .map(|budget| budget.saturating_sub(self.elapsed));
+        match (by_generations, by_budget) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    // One summary line
+    fn summary(&self) -> String {
+        format!(
+            "Generation {} of {}: best {}, mean {:.1}, {:.0} generations/s, {}",
+            self.generation,
+            self.generations,
+            self.best.fitness,
+            self.mean(),
+            self.rate(),
+            left(self.eta())
+        )
+    }
+}
+
+/// Where a search without generations, such as the exact one, stands.
+pub struct Search {
+    /// Nodes visited so far
+    pub nodes: u64,
+    /// Score of the best roster known so far
+    pub best: Option<i32>,
+    /// No roster scores below this
+    pub lower: i32,
+    /// Time spent in this search
+    pub elapsed: Duration,
+    pub budget: Duration,
+}
+
+impl Search {
+    // One summary line, without the time left once the search is `done`
+    fn summary(&self, done: bool) -> String {
+        let best = match self.best {
+            Some(best) => format!("best {}, gap at most {}", best, best - self.lower),
+            None => format!("nothing found yet, bound {}", self.lower),
+        };
+        let mut text = format!(
+            "{} nodes, {:.0} nodes/s, {}",
+            self.nodes,
+            self.nodes as f64 / self.elapsed.as_secs_f64().max(1e-9),
+            best
+        );
+        if !done {
+            text.push_str(&format!(
+                ", {}",
+                left(Some(self.budget.saturating_sub(self.elapsed)))
+            ));
+        }
+        text
+    }
+}
+
+/// A duration in the largest units that keep it short, such as `42s` or `3m05s`.
+pub fn clock(time: Duration) -> String {
+    let seconds = time.as_secs();
+    match seconds {
+        0..60 => format!("{}s", seconds),
+        60..3600 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h{:02}m", seconds / 3600, seconds / 60 % 60),
+    }
+}
+
+/// How long is left, in words.
+pub fn left(eta: Option<Duration>) -> String {
+    match eta {
+        Some(eta) => format!("about {} left", clock(eta)),
+        None => "time left unknown".to_string(),
+    }
+}
+
+/// Prints a run's progress as its [`Progress`] asks.
+pub struct Reporter {
+    progress: Progress,
+    /// Set while the dashboard owns the terminal; the run hands it keys and frames itself
+    pub dashboard: Option<Dashboard>,
+    // When the last line or bar went out, and at which generation
+    last: Option<(Instant, usize)>,
+    bar: bool,
+}
+
+impl Reporter {
+    /// Start reporting, taking over the terminal for the dashboard.
+    pub fn start(progress: Progress) -> Reporter {
+        let mut reporter = Reporter {
+            progress,
+            dashboard: None,
+            last: None,
+            bar: false,
+        };
+        if progress == Progress::Dashboard {
+            match Dashboard::start() {
+                Ok(dashboard) => reporter.dashboard = Some(dashboard),
+                Err(e) => {
+                    println!("Could not start the dashboard: {}", e);
+                    reporter.progress = Progress::Seconds(5.0);
+                }
+            }
+        }
+        reporter
+    }
+
+    /// Report one generation.  The dashboard is drawn by the run instead.
+    pub fn update(&mut self, frame: &Frame) {
+        let last = frame.generation >= frame.generations;
+        if !self.due(frame.generation, 1, last) {
+            return;
+        }
+        if self.progress != Progress::Bar {
+            println!("{}", frame.summary());
+            return;
+        }
+        let done = match frame.generations {
+            0 => 1.0,
+            gens => frame.generation as f64 / gens as f64,
+        };
+        self.draw_bar(
+            done,
+            &format!(
+                "{} of {}, {:.0}/s, {}, best {}",
+                frame.generation,
+                frame.generations,
+                frame.rate(),
+                left(frame.eta()),
+                frame.best.fitness
+            ),
+        );
+    }
+
+    /// Report a search without generations, always when it is `done`.
+    pub fn search(&mut self, search: &Search, done: bool) {
+        if !self.due(search.nodes as usize, 1000, done) {
+            return;
+        }
+        if self.progress != Progress::Bar {
+            println!("{}", search.summary(done));
+            return;
+        }
+        let spent = search.elapsed.as_secs_f64() / search.budget.as_secs_f64().max(1e-9);
+        self.draw_bar(
+            if done { 1.0 } else { spent.min(1.0) },
+            &search.summary(done),
+        );
+    }
+
+    // Whether a report is due at `count` generations or nodes, counting `step` of them as one
+    // for every:N, and noting the time and count when it is
+    fn due(&mut self, count: usize, step: usize, last: bool) -> bool {
+        let due = match (self.progress, self.last) {
+            (Progress::Quiet | Progress::Dashboard, _) => false,
+            _ if last => true,
+            (Progress::Verbose, _) | (_, None) => true,
+            (Progress::Generations(gens), Some((_, at))) => count >= at + gens * step,
+            (Progress::Seconds(seconds), Some((time, _))) => {
+                time.elapsed().as_secs_f64() >= seconds
+            }
+            (Progress::Bar, Some((time, _))) => time.elapsed() >= BAR_REFRESH,
+        };
+        if due {
+            self.last = Some((Instant::now(), count));
+        }
+        due
+    }
+
+    // Redraw the bar, `done` of the way along
+    fn draw_bar(&mut self, done: f64, text: &str) {
+        let filled = ((done * BAR_WIDTH as f64) as usize).min(BAR_WIDTH);
+        print!(
+            "\r[{}{}] {:>3.0}% {}\x1b[K",
+            "#".repeat(filled),
+            ".".repeat(BAR_WIDTH - filled),
+            done * 100.0,
+            text
+        );
+        io::stdout().flush().ok();
+        self.bar = true;
+    }
+
+    /// End the report: finish the bar's line and give the terminal back.
+    pub fn finish(&mut self) {
+        if self.bar {
+            println!();
+            self.bar = false;
+        }
+        self.dashboard = None;
+    }
+}
@@ -0,0 +1,121 @@
+//! The group's requested order of characters in each trait.
+
+use crate::cards::Pairing;
+use crate::flavour::{self, Flavour};
+use crate::metric::RankMetric;
+use crate::rules::{Targets, TRAITS};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+/// Requested order of characters, strongest first, for each of the four traits.
+///
+/// The rankings file lists each trait as a block, separated by blank lines:
+///
+/// ```text
+/// Might:
+/// Herman
+/// John
+/// ...
+/// ```
+#[derive(Debug, Clone)]
+pub struct Rankings {
+    traits: HashMap<String, Vec<String>>,
+    /// How far an order is from the requested one; not part of the file
+    pub metric: RankMetric,
+    /// What the averages aim for; not part of the file either
+    pub targets: Targets,
+    /// Which characters share a two-sided card
+    pub pairing: Pairing,
+    /// Printed details of the characters, from the companion flavour file
+    pub flavour: Flavour,
+}
+
+impl Rankings {
+    /// Read and validate a rankings file.
+    pub fn load(file: &str) -> Result<Rankings, String> {
+        let buffer =
+            fs::read_to_string(file).map_err(|e| format!("Could not read {}: {}", file, e))?;
+        Rankings::parse(&buffer)
+    }
+
+    /// Parse rankings text.  Every trait must be present and rank the same characters.
+    pub fn parse(buffer: &str) -> Result<Rankings, String> {
+        let mut traits: HashMap<String, Vec<String>> = HashMap::new();
+
+        for chunk in buffer.split("\n\n").filter(|x| !x.trim().is_empty()) {
+            let mut attribute = String::new();
+            let mut ranking = Vec::new();
+            for line in chunk.split('\n').filter(|x| !x.is_empty()).enumerate() {
+                if line.0 == 0 {
+                    attribute = line
+                        .1
+                        .strip_suffix(':')
+                        .ok_or(format!("Expected a trait header, found {}", line.1))?
+                        .to_string();
+                } else {
+                    ranking.push(line.1.to_string());
+                }
+            }
+            traits.insert(attribute, ranking);
+        }
+
+        // Validate 4 categories present
+        if traits.len() != 4 || !TRAITS.iter().all(|x| traits.contains_key(*x)) {
+            return Err(format!("Rankings must list exactly {}", TRAITS.join(", ")));
+        }
+
+        // Validate all characters present in each category
+        let set: HashSet<&String> = HashSet::from_iter(traits.get("Might").unwrap().iter());
+        for (attr, ranking) in traits.iter() {
+            if ranking.len() != set.len() || HashSet::from_iter(ranking.iter()) != set {
+                return Err(format!(
+                    "{} does not rank the same characters as Might",
+                    attr
+                ));
+            }
+        }
+
+        // Sheets and flavour files put a name and a colon on its own line, which must not read as
+        // a flavour field
+        if let Some(name) = set.iter().find(|x| flavour::is_field(&format!("{}:", x))) {
+            return Err(format!(
+                "{} cannot be a character name, it reads as a flavour field",
+                name
+            ));
+        }
+
+        Ok(Rankings {
+            traits,
+            metric: RankMetric::default(),
+            targets: Targets::default(),
+            pairing: Pairing::default(),
+            flavour: Flavour::default(),
+        })
+    }
+
+    /// The ranking of one trait, strongest first.
+    pub fn get(&self, attr: &str) -> Option<&Vec<String>> {
+        self.traits.get(attr)
+    }
+
+    /// Every character name, in the order of the Might ranking.
+    pub fn names(&self) -> &[String] {
+        self.traits.get("Might").unwrap()
+    }
+
+    /// A copy with the characters at `place` and `place + 1` of one trait's ranking swapped.
+    pub fn swapped(&self, attr: &str, place: usize) -> Rankings {
+        let mut rankings = self.clone();
+        rankings
+            .traits
+            .get_mut(attr)
+            .unwrap()
+            .swap(place, place + 1);
+        rankings
+    }
+
+    /// Each trait with its ranking, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<String>)> {
+        self.traits.iter()
+    }
+}
@@ -0,0 +1,268 @@
+//! Published characters, read from a file the user supplies, to hold generated rosters up against.
+//!
+//! The file is in the [`crate::sheets`] format, one edition per `## NAME` line.  Published
+//! characters need not follow this crate's rules, so each is reported with the rules it breaks
+//! rather than rejected.  An edition's averages can also replace the default [`Targets`].
+//!
+//! No characters are bundled: the comparison is only as good as the tracks and starting positions
+//! it reads, so they have to be copied from the cards themselves.
+
+use crate::individual::Individual;
+use crate::rules::{self, Stats, Targets, TRAITS};
+use crate::sheets;
+use std::fs;
+
+/// The published characters of one edition of the game.
+#[derive(Debug, Clone)]
+pub struct Edition {
+    pub name: String,
+    pub characters: Vec<(String, Stats)>,
+}
+
+/// Read the editions of a reference file, in file order.
+pub fn load(file: &str) -> Result<Vec<Edition>, String> {
+    let buffer = fs::read_to_string(file).map_err(|e| {
+        format!(
+            "Could not read {}: {}.  Copy the published characters from the cards into it as \
+             character sheets, one edition per \"## NAME\" line",
+            file, e
+        )
+    })?;
+    parse(&buffer).map_err(|e| format!("{}: {}", file, e))
+}
+
+/// Parse editions, each a `## NAME` line followed by its characters.
+pub fn parse(buffer: &str) -> Result<Vec<Edition>, String> {
+    let mut editions: Vec<Edition> = Vec::new();
+    let mut text = String::new();
+    for line in buffer.lines().chain(["## end"]) {
+        let Some(name) = line.strip_prefix("## ") else {
+            if editions.is_empty() && !line.trim().is_empty() && !line.starts_with('#') {
+                return Err(format!("Expected an edition header, found {}", line));
+            }
+            text.push_str(line);
+            text.push('\n');
+            continue;
+        };
+        if let Some(edition) = editions.last_mut() {
+            edition.characters = sheets::parse_rosters(&text)
+                .map_err(|e| format!("{}: {}", edition.name, e))?
+                .into_iter()
+                .flatten()
+                .collect();
+        }
+        text.clear();
+        editions.push(Edition {
+            name: name.to_string(),
+            characters: Vec::new(),
+        });
+    }
+    editions.pop();
+    if editions.is_empty() {
+        return Err("No editions".to_string());
+    }
+    Ok(editions)
+}
+
+/// Targets accepted by [`parse_targets`].
+pub const TARGETS: &str = "default, reference[:EDITION], MIGHT,SPEED,KNOW,SANITY[,TOTAL]";
+
+/// Build targets from configuration text: the defaults, the averages of an edition in the
+/// reference `file` numbered from 1 in file order, or explicit values for [`Targets::parse`].
+pub fn parse_targets(text: &str, file: &str) -> Result<Targets, String> {
+    if text == "default" {
+        return Ok(Targets::default());
+    }
+    let Some(rest) = text.strip_prefix("reference") else {
+        return Targets::parse(text);
+    };
+    let number = match rest.strip_prefix(':') {
+        Some(number) => number
+            .parse::<usize>()
+            .map_err(|_| format!("Bad edition: {}", number))?,
+        None if rest.is_empty() => 1,
+        None => return Err(format!("Unknown targets {}, expected {}", text, TARGETS)),
+    };
+    let editions = load(file)?;
+    match number.checked_sub(1).and_then(|i| editions.get(i)) {
+        Some(edition) => Ok(edition.targets()),
+        None => Err(format!(
+            "No edition {}, there are {}",
+            number,
+            editions.len()
+        )),
+    }
+}
+
+impl Edition {
+    /// Targets at this edition's averages: the mean starting value of each trait and the mean
+    /// track total.
+    pub fn targets(&self) -> Targets {
+        let summary = Summary::of(&self.characters);
+        Targets {
+            starts: summary.starts,
+            total: Some(summary.total),
+        }
+    }
+
+    /// Each character's starting values, track total and constitution, then the roster's
+    /// averages and spreads and how they score against `targets`.  With `roster`, its averages
+    /// follow for comparison.
+    pub fn report(&self, targets: &Targets, roster: Option<&Individual>) -> String {
+        let width = self
+            .characters
+            .iter()
+            .map(|x| x.0.len())
+            .max()
+            .unwrap_or(0)
+            .max("Population best".len());
+        let mut text = format!("{}: {} characters\n", self.name, self.characters.len());
+        text.push_str(&format!(
+            "{:<width$} {:>6} {:>6} {:>6} {:>6} {:>6} {:>6}\n",
+            "Character",
+            TRAITS[0],
+            TRAITS[1],
+            TRAITS[2],
+            TRAITS[3],
+            "Total",
+            "Const",
+            width = width
+        ));
+        let mut breaks = Vec::new();
+        for (name, stats) in self.characters.iter() {
+            let starts = TRAITS.map(|attr| {
+                let tup = stats.get(attr).unwrap();
+                tup.0[tup.1]
+            });
+            text.push_str(&format!(
+                "{:<width$} {:>6} {:>6} {:>6} {:>6} {:>6} {:>6}\n",
+                name,
+                starts[0],
+                starts[1],
+                starts[2],
+                starts[3],
+                rules::total(stats),
+                rules::constitution(stats),
+                width = width
+            ));
+            breaks.extend(rules::character_violations(name, stats));
+        }
+
+        let summary = Summary::of(&self.characters);
+        text.push_str(&summary.line("Average", width));
+        text.push_str(&format!(
+            "{:<width$} {:>6.2} {:>6.2} {:>6.2} {:>6.2} {:>6.1}\n",
+            "Spread",
+            summary.spreads[0],
+            summary.spreads[1],
+            summary.spreads[2],
+            summary.spreads[3],
+            summary.total_spread,
+            width = width
+        ));
+        if let Some(ind) = roster {
+            let characters: Vec<(String, Stats)> = ind
+                .names()
+                .into_iter()
+                .map(|name| {
+                    let stats = ind.stats(&name).unwrap().clone();
+                    (name, stats)
+                })
+                .collect();
+            text.push_str(&Summary::of(&characters).line("Population best", width));
+        }
+
+        text.push_str(&format!(
+            "Average penalty {} against targets {}, four indexes {}, totals diff {}\n",
+            summary.penalty(targets),
+            targets.describe(),
+            summary.four_indexes,
+            summary.totals_diff
+        ));
+        if breaks.is_empty() {
+            text.push_str("Every character follows the rules\n");
+        } else {
+            text.push_str("Rule breaks:\n");
+            for violation in breaks {
+                text.push_str(&format!("\t{}\n", violation));
+            }
+        }
+        text
+    }
+}
+
+// The roster-wide numbers Individual::score works from, for characters outside any individual
+struct Summary {
+    count: usize,
+    starts: [f32; 4],
+    spreads: [f32; 4],
+    total: f32,
+    total_spread: f32,
+    four_indexes: usize,
+    totals_diff: usize,
+    sums: [i32; 4],
+    totals: i32,
+}
+
+impl Summary {
+    fn of(characters: &[(String, Stats)]) -> Summary {
+        let count = characters.len().max(1);
+        let values = |f: &dyn Fn(&Stats) -> i32| -> Vec<i32> {
+            characters.iter().map(|(_, stats)| f(stats)).collect()
+        };
+        let mean = |xs: &[i32]| xs.iter().sum::<i32>() as f32 / count as f32;
+        let spread = |xs: &[i32]| {
+            let mean = mean(xs);
+            (xs.iter().map(|x| (*x as f32 - mean).powi(2)).sum::<f32>() / count as f32).sqrt()
+        };
+
+        let starts = TRAITS.map(|attr| {
+            values(&|stats| {
+                let tup = stats.get(attr).unwrap();
+                tup.0[tup.1]
+            })
+        });
+        let totals = values(&|stats| rules::total(stats));
+        let tens = values(&|stats| rules::constitution(stats) as i32)
+            .iter()
+            .filter(|x| **x == 10)
+            .count();
+        Summary {
+            count,
+            starts: [0, 1, 2, 3].map(|i| mean(&starts[i])),
+            spreads: [0, 1, 2, 3].map(|i| spread(&starts[i])),
+            total: mean(&totals),
+            total_spread: spread(&totals),
+            four_indexes: characters
+                .iter()
+                .map(|(_, stats)| stats.values().filter(|tup| tup.1 == 4).count())
+                .sum(),
+            totals_diff: tens.abs_diff(characters.len() - tens),
+            sums: [0, 1, 2, 3].map(|i| starts[i].iter().sum()),
+            totals: totals.iter().sum(),
+        }
+    }
+
+    fn line(&self, label: &str, width: usize) -> String {
+        format!(
+            "{:<width$} {:>6.2} {:>6.2} {:>6.2} {:>6.2} {:>6.1}\n",
+            label,
+            self.starts[0],
+            self.starts[1],
+            self.starts[2],
+            self.starts[3],
+            self.total,
+            width = width
+        )
+    }
+
+    // The avg_score an individual with these characters would get
+    fn penalty(&self, targets: &Targets) -> i32 {
+        let starts: i32 = TRAITS
+            .iter()
+            .zip(self.sums)
+            .map(|(attr, sum)| targets.average_penalty(attr, sum, self.count))
+            .sum();
+        starts + targets.total_penalty(self.totals, self.count)
+    }
+}
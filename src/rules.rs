@@ -0,0 +1,261 @@
+//! The rules every character sheet must follow.
+
+use std::cmp;
+use std::collections::HashMap;
+
+/// The four traits of every character, in sheet order.
+pub const TRAITS: [&str; 4] = ["Might", "Speed", "Know", "Sanity"];
+
+/// A trait track: eight values from lowest to highest, and the index of the starting value.
+pub type Track = (Vec<i32>, usize);
+
+/// The four tracks of one character, keyed by trait name.
+pub type Stats = HashMap<String, Track>;
+
+/// Starting values of the four traits always add up to this.
+pub const STARTING_SUM: i32 = 15;
+
+/// The values of all four tracks add up to somewhere in this range.
+pub const MIN_TOTAL: i32 = 145;
+pub const MAX_TOTAL: i32 = 155;
+
+/// True when a track breaks none of the rules in [`track_violations`].
+pub fn valid_track(track: &[i32]) -> bool {
+    track_violations(track).is_empty()
+}
+
+/// Every rule a single track breaks, e.g. "decreases from 5 to 4 at position 6".
+pub fn track_violations(track: &[i32]) -> Vec<String> {
+    let mut violations = Vec::new();
+
+    // Eight values, each from 1 to 8
+    if track.len() != 8 {
+        violations.push(format!("has {} values instead of 8", track.len()));
+        return violations;
+    }
+    if !track.iter().all(|x| (1..=8).contains(x)) {
+        violations.push("has a value outside 1 to 8".to_string());
+    }
+
+    // Never decreasing, and never climbing by more than 2
+    for (i, w) in track.windows(2).enumerate() {
+        if w[1] < w[0] {
+            violations.push(format!(
+                "decreases from {} to {} at position {}",
+                w[0],
+                w[1],
+                i + 2
+            ));
+        } else if w[1] > w[0] + 2 {
+            violations.push(format!(
+                "climbs from {} to {} at position {}",
+                w[0],
+                w[1],
+                i + 2
+            ));
+        }
+    }
+
+    // Lowest number must be 1 to 4, highest number must be 5 to 8
+    if track[0] > 4 {
+        violations.push(format!("lowest value {} is above 4", track[0]));
+    }
+    if track[7] < 5 {
+        violations.push(format!("highest value {} is below 5", track[7]));
+    }
+
+    violations
+}
+
+/// The lowest legal starting value of a trait.
+pub fn minimum_start(attr: &str) -> i32 {
+    if attr == "Might" {
+        2
+    } else {
+        3
+    }
+}
+
+/// True when the starting indexes and values of a character fit together: every index from 2 to
+/// 4, starting values summing to 15, a constitution of 10 or 11 and no starting value too low.
+pub fn valid_indexes(stats: &Stats) -> bool {
+    // Indexes are all >= 2 and <= 4
+    if !stats.values().all(|x| x.1 >= 2 && x.1 <= 4) {
+        return false;
+    }
+
+    // Starting values sum to 15
+    if stats.values().map(|x| x.0[x.1]).sum::<i32>() != STARTING_SUM {
+        return false;
+    }
+
+    // Constitution is 10 or 11
+    if !(10..=11).contains(&constitution(stats)) {
+        return false;
+    }
+
+    // Starting values are at least either 2 or 3
+    stats
+        .iter()
+        .all(|(attr, tup)| tup.0[tup.1] >= minimum_start(attr))
+}
+
+/// What a roster's averages aim for.  The defaults suit the group's taste; [`crate::reference`]
+/// can derive them from the published characters instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Targets {
+    /// Average starting value of each trait, in [`TRAITS`] order
+    pub starts: [f32; 4],
+    /// Average track total of a character, if it matters
+    pub total: Option<f32>,
+}
+
+impl Default for Targets {
+    fn default() -> Targets {
+        Targets {
+            starts: [3.25, 4.0, 4.0, 4.0],
+            total: None,
+        }
+    }
+}
+
+impl Targets {
+    /// Configuration text that [`Targets::parse`] turns back into these targets.
+    pub fn describe(&self) -> String {
+        let mut text: Vec<String> = self.starts.iter().map(|x| x.to_string()).collect();
+        if let Some(total) = self.total {
+            text.push(total.to_string());
+        }
+        text.join(",")
+    }
+
+    /// Read targets such as `3.25,4,4,4` or, with a track total, `3.25,4,4,4,150`.
+    pub fn parse(text: &str) -> Result<Targets, String> {
+        let values: Vec<f32> = text
+            .split(',')
+            .map(|x| x.trim().parse().map_err(|_| format!("Bad target: {}", x)))
+            .collect::<Result<_, _>>()?;
+        match values[..] {
+            [m, s, k, x] => Ok(Targets {
+                starts: [m, s, k, x],
+                total: None,
+            }),
+            [m, s, k, x, total] => Ok(Targets {
+                starts: [m, s, k, x],
+                total: Some(total),
+            }),
+            _ => Err("Targets need four starting averages and optionally a total".to_string()),
+        }
+    }
+
+    /// How far the average starting value of a trait strays from its target, times 10 and
+    /// rounded down, given the sum of `count` starting values.
+    pub fn average_penalty(&self, attr: &str, sum: i32, count: usize) -> i32 {
+        let target = self.starts[TRAITS.iter().position(|x| *x == attr).unwrap()];
+        ((sum as f32 / count as f32 - target).abs() * 10.0) as i32
+    }
+
+    /// How far the average track total strays from its target, rounded down, given the sum of
+    /// `count` totals.  Totals run ten times larger than starting values, so this is not scaled.
+    pub fn total_penalty(&self, sum: i32, count: usize) -> i32 {
+        match self.total {
+            Some(target) => (sum as f32 / count as f32 - target).abs() as i32,
+            None => 0,
+        }
+    }
+}
+
+/// Strength of a track: the starting value plus the values around it, each step further away
+/// counting half as much.  See [`crate::individual::Individual::attr_score`].
+pub fn strength(tup: &Track) -> f32 {
+    let mut weighted_sum = tup.0[tup.1] as f32;
+    let mut weight: f32 = 0.5;
+    let mut offset = 1;
+    // Keep adding offsets while there are unused numbers above or below the index
+    while offset <= tup.1 || offset + tup.1 <= 7 {
+        if offset > tup.1 {
+            // If offsetting into death, the lower number is considered 0 and not added
+            // Not possible for upper offset to be over 7
+            weighted_sum += tup.0[tup.1 + offset] as f32 * weight;
+        } else {
+            // If offsetting over the max index, reuse the max number
+            weighted_sum +=
+                (tup.0[tup.1 - offset] + tup.0[cmp::min(tup.1 + offset, 7)]) as f32 * weight;
+        }
+        weight *= 0.5;
+        offset += 1;
+    }
+
+    weighted_sum
+}
+
+/// Sum of the starting indexes of a character.
+pub fn constitution(stats: &Stats) -> usize {
+    stats.values().map(|x| x.1).sum()
+}
+
+/// Sum of every value on every track of a character.
+pub fn total(stats: &Stats) -> i32 {
+    stats.values().map(|x| x.0.iter().sum::<i32>()).sum()
+}
+
+/// Every rule a character breaks, one line per problem, each starting with the character name.
+pub fn character_violations(name: &str, stats: &Stats) -> Vec<String> {
+    let mut violations = Vec::new();
+    for attr in TRAITS.iter() {
+        let tup = match stats.get(*attr) {
+            Some(tup) => tup,
+            None => {
+                violations.push(format!("{} has no {} track", name, attr));
+                continue;
+            }
+        };
+        for violation in track_violations(&tup.0) {
+            violations.push(format!("{} {} {}", name, attr, violation));
+        }
+        if tup.1 < 2 || tup.1 > 4 {
+            violations.push(format!(
+                "{} {} starts at position {}, not 3 to 5",
+                name,
+                attr,
+                tup.1 + 1
+            ));
+        } else if tup.0[tup.1] < minimum_start(attr) {
+            violations.push(format!(
+                "{} {} starts at {}, below {}",
+                name,
+                attr,
+                tup.0[tup.1],
+                minimum_start(attr)
+            ));
+        }
+    }
+
+    let starting: i32 = stats
+        .values()
+        .filter(|x| x.1 < x.0.len())
+        .map(|x| x.0[x.1])
+        .sum();
+    if starting != STARTING_SUM {
+        violations.push(format!(
+            "{} starting values sum to {}, not {}",
+            name, starting, STARTING_SUM
+        ));
+    }
+    let constitution = constitution(stats);
+    if !(10..=11).contains(&constitution) {
+        violations.push(format!(
+            "{} constitution is {}, not 10 or 11",
+            name, constitution
+        ));
+    }
+    let total = total(stats);
+    if !(MIN_TOTAL..=MAX_TOTAL).contains(&total) {
+        violations.push(format!(
+            "{} track total is {}, not {} to {}",
+            name, total, MIN_TOTAL, MAX_TOTAL
+        ));
+    }
+
+    violations
+}
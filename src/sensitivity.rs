@@ -0,0 +1,237 @@
+//! How much the best roster depends on the exact rankings.
+//!
+//! Each variant swaps two neighbouring names in one trait's ranking and re-optimizes the current
+//! best under the changed rankings: a short run of a population seeded with it, if asked for, then
+//! polishing.  A baseline does the same under the unchanged rankings, so that tracks the search
+//! would move anyway are not taken for sensitivity.  Only starting values and indexes are
+//! compared: the rest of a track can often shift without changing its strength.  A character
+//! whose starts differ from the baseline under many swaps is fragile; one whose starts never do is
+//! stable.
+//!
+//! Polishing alone is deterministic, so every difference comes from the swap.  A seeded run
+//! wanders between rosters of equal fitness, and the report says how far the baseline itself
+//! moved to show how much of the difference is noise.
+//!
+//! The whole analysis counts as one run for Ctrl-C, which stops it straight away, keeping the
+//! variants finished before it.
+
+use crate::diff;
+use crate::individual::Individual;
+use crate::polish;
+use crate::population::{Betrayal, Population};
+use crate::rankings::Rankings;
+use crate::rules::TRAITS;
+use crate::stopping::{self, StopReason, StoppingCriteria};
+
+/// Share of the swaps that must change a character's tracks for it to count as fragile.
+pub const FRAGILE: f32 = 0.25;
+
+// Individuals of the seeded population grown from the best
+const SEED_COUNT: usize = 1000;
+
+/// One swap of two neighbouring names and what re-optimizing made of it.
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub attr: &'static str,
+    /// The names swapped, in their original order
+    pub swapped: (String, String),
+    pub best: Individual,
+    /// Traits whose starting value or index differs from the baseline, by character
+    pub changed: Vec<(String, &'static str)>,
+}
+
+/// The baseline and every variant re-optimized so far.
+#[derive(Debug, Clone)]
+pub struct Sensitivity {
+    pub baseline: Individual,
+    /// Traits whose start the baseline moved away from the best it was grown from
+    pub drift: Vec<(String, &'static str)>,
+    pub variants: Vec<Variant>,
+    /// Whether every swap was tried before Ctrl-C
+    pub complete: bool,
+}
+
+/// Re-optimize the population's best under every adjacent swap of every trait, running
+/// `generations` of a seeded population before polishing each.  Ctrl-C drops the variant in
+/// progress and returns those finished.
+pub fn analyse(pop: &Population, generations: usize) -> Sensitivity {
+    let outer = stopping::start();
+    let sensitivity = variants(pop, generations);
+    stopping::finish(outer);
+    sensitivity
+}
+
+// The analysis itself, returning early when interrupted
+fn variants(pop: &Population, generations: usize) -> Sensitivity {
+    let best = pop.best();
+    let rankings = &pop.betrayal.rankings;
+    let mut sensitivity = Sensitivity {
+        baseline: best.clone(),
+        drift: Vec::new(),
+        variants: Vec::new(),
+        complete: false,
+    };
+    let Some(baseline) = optimize(pop, best, rankings.clone(), generations) else {
+        return sensitivity;
+    };
+    sensitivity.drift = moved(best, &baseline);
+    sensitivity.baseline = baseline;
+
+    for attr in TRAITS.iter() {
+        let ranking = rankings.get(attr).unwrap();
+        for place in 0..ranking.len() - 1 {
+            let swapped = rankings.swapped(attr, place);
+            let Some(ind) = optimize(pop, best, swapped, generations) else {
+                return sensitivity;
+            };
+            let changed = moved(&sensitivity.baseline, &ind);
+            sensitivity.variants.push(Variant {
+                attr,
+                swapped: (ranking[place].clone(), ranking[place + 1].clone()),
+                best: ind,
+                changed,
+            });
+        }
+    }
+    sensitivity.complete = true;
+    sensitivity
+}
+
+// Traits whose starting value or index differs between two rosters
+fn moved(before: &Individual, after: &Individual) -> Vec<(String, &'static str)> {
+    diff::diff(before, after)
+        .unwrap()
+        .traits
+        .into_iter()
+        .filter(|x| x.before.0[x.before.1] != x.after.0[x.after.1] || x.index_moved())
+        .map(|x| (x.name, x.attr))
+        .collect()
+}
+
+// The best roster under `rankings`, grown from `best`, or None when interrupted on the way
+fn optimize(
+    pop: &Population,
+    best: &Individual,
+    rankings: Rankings,
+    generations: usize,
+) -> Option<Individual> {
+    let locks = pop.betrayal.locks.clone();
+    let mut ind = if generations > 0 {
+        let mut seeded = Population::seeded(
+            rankings.clone(),
+            locks.clone(),
+            std::slice::from_ref(best),
+            SEED_COUNT,
+        );
+        seeded.progress = pop.progress;
+        if stopping::interrupted() {
+            return None;
+        }
+        let criteria = StoppingCriteria {
+            generations,
+            ..Default::default()
+        };
+        if seeded.run(&criteria) == StopReason::Interrupted {
+            return None;
+        }
+        seeded.best().clone()
+    } else {
+        best.clone()
+    };
+    polish::polish(&mut ind, &Betrayal { rankings, locks });
+    (!stopping::interrupted()).then_some(ind)
+}
+
+impl Sensitivity {
+    /// Each swap with the fitness it reached and the tracks it changed, then how often each
+    /// character's traits changed and which characters are stable or fragile.
+    pub fn report(&self) -> String {
+        let describe = |changed: &[(String, &str)]| match changed {
+            [] => "no starts changed".to_string(),
+            changed => {
+                let traits: Vec<String> = changed
+                    .iter()
+                    .map(|(name, attr)| format!("{} {}", name, attr))
+                    .collect();
+                format!("changed {}", traits.join(", "))
+            }
+        };
+        let mut text = format!(
+            "Baseline fitness {}, {} from the best\n",
+            self.baseline.fitness,
+            describe(&self.drift)
+        );
+        for variant in self.variants.iter() {
+            text.push_str(&format!(
+                "{}: {}/{} fitness {}, {}\n",
+                variant.attr,
+                variant.swapped.0,
+                variant.swapped.1,
+                variant.best.fitness,
+                describe(&variant.changed)
+            ));
+        }
+        if !self.complete {
+            text.push_str("Stopped before every swap was tried\n");
+        }
+        if self.variants.is_empty() {
+            return text;
+        }
+
+        let names = self.baseline.names();
+        let width = names
+            .iter()
+            .map(|x| x.len())
+            .max()
+            .unwrap()
+            .max("Character".len());
+        text.push_str(&format!(
+            "\nSwaps changing each start, out of {}\n{:<width$} {:>6} {:>6} {:>6} {:>6} {:>6}\n",
+            self.variants.len(),
+            "Character",
+            TRAITS[0],
+            TRAITS[1],
+            TRAITS[2],
+            TRAITS[3],
+            "Any",
+            width = width
+        ));
+        let mut stable = Vec::new();
+        let mut fragile = Vec::new();
+        for name in names.iter() {
+            let counts = TRAITS.map(|attr| {
+                self.variants
+                    .iter()
+                    .filter(|v| v.changed.iter().any(|x| x.0 == *name && x.1 == attr))
+                    .count()
+            });
+            let any = self
+                .variants
+                .iter()
+                .filter(|v| v.changed.iter().any(|x| x.0 == *name))
+                .count();
+            text.push_str(&format!(
+                "{:<width$} {:>6} {:>6} {:>6} {:>6} {:>6}\n",
+                name,
+                counts[0],
+                counts[1],
+                counts[2],
+                counts[3],
+                any,
+                width = width
+            ));
+            if any == 0 {
+                stable.push(name.as_str());
+            } else if any as f32 > FRAGILE * self.variants.len() as f32 {
+                fragile.push(name.as_str());
+            }
+        }
+        let list = |names: &[&str]| match names {
+            [] => "none".to_string(),
+            names => names.join(", "),
+        };
+        text.push_str(&format!("Stable: {}\n", list(&stable)));
+        text.push_str(&format!("Fragile: {}\n", list(&fragile)));
+        text
+    }
+}
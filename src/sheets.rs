@@ -0,0 +1,225 @@
+//! Character sheets as text, for designers to read, hand-write and keep under version control.
+//!
+//! The layout follows the rankings file: a block per character, headed by its name, with one
+//! line per trait listing the eight track values and the starting value in brackets.
+//!
+//! ```text
+//! # Individual 5799, fitness 50
+//!
+//! Anthony:
+//! Might: 2 3 [3] 4 5 6 7 7
+//! Speed: 1 3 3 [4] 6 6 7 7
+//! Know: 2 2 4 [4] 6 6 8 8
+//! Sanity: 2 2 3 [4] 6 6 6 8
+//!
+//! Herman:
+//! ...
+//! ```
+//!
+//! Lines starting with `#` are comments, and each one that follows a character block starts a new
+//! roster, so a file can hold several individuals, such as an exported hall of fame.  The locks
+//! file uses the same blocks but may leave traits out, and exported sheets add each character's
+//! [`crate::flavour`] lines after its tracks.
+
+use crate::flavour::{self, Flavour};
+use crate::individual::Individual;
+use crate::rankings::Rankings;
+use crate::rules::{self, Stats, Track, TRAITS};
+use std::collections::HashMap;
+use std::fs;
+
+/// Every character of an individual as sheet blocks, in name order, each followed by its
+/// flavour lines.
+pub fn write(ind: &Individual, flavour: &Flavour) -> String {
+    let mut text = String::new();
+    for name in ind.names() {
+        text.push_str(&format!("{}:\n{}", name, ind.sheet(&name)));
+        for line in flavour.lines(&name) {
+            text.push_str(&format!("{}\n", line));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Read a sheets file into rosters.  See [`parse`].
+pub fn load(file: &str, rankings: &Rankings) -> Result<Vec<Individual>, String> {
+    let buffer = fs::read_to_string(file).map_err(|e| format!("Could not read {}: {}", file, e))?;
+    parse(&buffer, rankings)
+}
+
+/// Parse sheets text into rosters scored against `rankings`.  Each roster must give every
+/// character in the rankings all four traits and follow the rules.  The individuals have id 0
+/// and sit at the origin until they join a population.
+pub fn parse(buffer: &str, rankings: &Rankings) -> Result<Vec<Individual>, String> {
+    let names = rankings.names();
+    let mut rosters = Vec::new();
+    for (i, blocks) in parse_rosters(buffer)?.into_iter().enumerate() {
+        let mut chars = HashMap::new();
+        for (name, stats) in blocks {
+            if !names.contains(&name) {
+                return Err(format!("{} is not in the rankings", name));
+            }
+            if let Some(attr) = TRAITS.iter().find(|x| !stats.contains_key(**x)) {
+                return Err(format!("{} has no {} track", name, attr));
+            }
+            chars.insert(name, stats);
+        }
+        if let Some(name) = names.iter().find(|x| !chars.contains_key(*x)) {
+            return Err(format!("Roster {} has no sheet for {}", i + 1, name));
+        }
+
+        let ind = Individual::from_chars(0, 0, 0, chars, rankings);
+        if let Some(violation) = ind.violations().first() {
+            return Err(format!("Roster {} breaks a rule: {}", i + 1, violation));
+        }
+        rosters.push(ind);
+    }
+
+    if rosters.is_empty() {
+        return Err("No character sheets found".to_string());
+    }
+    Ok(rosters)
+}
+
+/// Character blocks grouped into rosters, without checking them against anything.  Blocks may
+/// leave traits out.  Flavour lines are skipped; the flavour file is where they are read from.
+pub fn parse_rosters(buffer: &str) -> Result<Vec<Vec<(String, Stats)>>, String> {
+    let mut rosters: Vec<Vec<(String, Stats)>> = vec![Vec::new()];
+    for line in buffer.lines().map(|x| x.trim()).filter(|x| !x.is_empty()) {
+        let roster = rosters.last_mut().unwrap();
+        if line.starts_with('#') {
+            if !roster.is_empty() {
+                rosters.push(Vec::new());
+            }
+            continue;
+        }
+
+        // Flavour lines come first, as an empty one such as "Hobbies:" looks like a header
+        if flavour::is_field(line) {
+            continue;
+        }
+        if let Some(name) = line.strip_suffix(':') {
+            if roster.iter().any(|x| x.0 == name) {
+                return Err(format!("{} appears twice in one roster", name));
+            }
+            roster.push((name.to_string(), HashMap::new()));
+            continue;
+        }
+        let (name, stats) = roster
+            .last_mut()
+            .ok_or(format!("Expected a character name header, found {}", line))?;
+        let (attr, track) =
+            parse_track(line).ok_or(format!("Could not parse trait for {}: {}", name, line))?;
+        if stats.insert(attr, track).is_some() {
+            return Err(format!("{} has a trait twice: {}", name, line));
+        }
+    }
+
+    rosters.retain(|x| !x.is_empty());
+    Ok(rosters)
+}
+
+/// Parse one trait line such as `Might: 2 2 [3] 4 5 6 7 8`.  The track must be legal and have
+/// exactly one starting value marked.
+pub fn parse_track(line: &str) -> Option<(String, Track)> {
+    let (attr, values) = line.split_once(':')?;
+    if !TRAITS.contains(&attr.trim()) {
+        return None;
+    }
+
+    let mut track = Vec::new();
+    let mut index = None;
+    for (i, value) in values.split_whitespace().enumerate() {
+        let value = match value.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
+            Some(marked) => {
+                if index.replace(i).is_some() {
+                    return None;
+                }
+                marked
+            }
+            None => value,
+        };
+        track.push(value.parse().ok()?);
+    }
+
+    if !rules::valid_track(&track) {
+        return None;
+    }
+
+    Some((attr.trim().to_string(), (track, index?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ga::CellularGa;
+    use crate::locks::Locks;
+    use crate::population::Betrayal;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const RANKINGS: &str = "Might:\nKyle Smith\nAnna\nBo\n\nSpeed:\nBo\nKyle Smith\nAnna\n\n\
+        Know:\nAnna\nBo\nKyle Smith\n\nSanity:\nKyle Smith\nBo\nAnna\n";
+
+    #[test]
+    fn round_trip() {
+        let rankings = Rankings::parse(RANKINGS).unwrap();
+        let betrayal = Betrayal {
+            rankings: rankings.clone(),
+            locks: Locks::default(),
+        };
+        let mut rng = StdRng::seed_from_u64(3);
+        let members = CellularGa::new(5, (10, 10, 3), &betrayal, &betrayal, &mut rng).members;
+        let flavour = Flavour::parse(
+            "Anna:\nAge: 30\nHobbies: Chess, Fishing\n",
+            rankings.names(),
+        )
+        .unwrap();
+
+        let text: String = members
+            .iter()
+            .map(|ind| format!("# Individual {}\n\n{}", ind.id, write(ind, &flavour)))
+            .collect();
+        let parsed = parse(&text, &rankings).unwrap();
+        assert_eq!(parsed.len(), members.len());
+        for (a, b) in members.iter().zip(parsed.iter()) {
+            assert_eq!(a.fitness, b.fitness);
+            for name in a.names() {
+                for attr in TRAITS.iter() {
+                    assert_eq!(a.track(&name, attr), b.track(&name, attr));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty_flavour_fields_are_not_headers() {
+        let rosters =
+            parse_rosters("Kyle:\nMight: 2 3 [4] 5 5 6 7 8\nHobbies:\nAge: 30\n").unwrap();
+        assert_eq!(rosters.len(), 1);
+        assert_eq!(rosters[0].len(), 1);
+        assert_eq!(rosters[0][0].0, "Kyle");
+    }
+
+    #[test]
+    fn flavour_fields_are_not_character_names() {
+        for name in ["Age", "Hobbies", "Birthday: March"] {
+            let error = Rankings::parse(&RANKINGS.replace("Anna", name))
+                .err()
+                .unwrap();
+            assert!(error.contains("flavour field"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn parse_track_needs_one_start() {
+        assert_eq!(
+            parse_track("Might: 2 2 [3] 4 5 6 7 8"),
+            Some(("Might".to_string(), (vec![2, 2, 3, 4, 5, 6, 7, 8], 2)))
+        );
+        assert_eq!(parse_track("Might: 2 2 3 4 5 6 7 8"), None);
+        assert_eq!(parse_track("Might: 2 [2] [3] 4 5 6 7 8"), None);
+        assert_eq!(parse_track("Luck: 2 2 [3] 4 5 6 7 8"), None);
+    }
+}
@@ -0,0 +1,71 @@
+//! Per-generation statistics written to CSV.
+
+use crate::individual::Individual;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::time::Duration;
+
+const HEADER: &str = "generation,elapsed_secs,best_fitness,mean_fitness,worst_fitness,\
+    best_rank_score,best_avg_score,best_four_indexes,best_totals_diff,best_pair_score,distinct_genomes";
+
+/// Appends one CSV row of population statistics every `interval` generations.
+pub struct StatsRecorder {
+    pub file: String,
+    pub interval: usize,
+    writer: BufWriter<File>,
+}
+
+impl StatsRecorder {
+    /// Create the CSV file, replacing any old one, and write its header.
+    pub fn create(file: &str, interval: usize) -> io::Result<StatsRecorder> {
+        let mut writer = BufWriter::new(File::create(file)?);
+        writeln!(writer, "{}", HEADER)?;
+
+        Ok(StatsRecorder {
+            file: file.to_string(),
+            interval: interval.max(1),
+            writer,
+        })
+    }
+
+    /// Write a row if `generation` falls on the interval.
+    pub fn record(
+        &mut self,
+        generation: usize,
+        elapsed: Duration,
+        pop: &[Individual],
+    ) -> io::Result<()> {
+        if !generation.is_multiple_of(self.interval) {
+            return Ok(());
+        }
+
+        let best = pop.iter().min_by_key(|x| x.fitness).unwrap();
+        let worst = pop.iter().map(|x| x.fitness).max().unwrap();
+        let mean = pop.iter().map(|x| x.fitness as f64).sum::<f64>() / pop.len() as f64;
+
+        writeln!(
+            self.writer,
+            "{},{:.3},{},{:.3},{},{},{},{},{},{},{:.4}",
+            generation,
+            elapsed.as_secs_f64(),
+            best.fitness,
+            mean,
+            worst,
+            best.rank_score,
+            best.avg_score,
+            best.four_indexes,
+            best.totals_diff,
+            best.pair_score,
+            distinct_genomes(pop)
+        )?;
+        // Flush every row so the file can be charted while the run is still going
+        self.writer.flush()
+    }
+}
+
+/// Number of distinct genomes as a fraction of the population size.
+pub fn distinct_genomes(pop: &[Individual]) -> f64 {
+    let keys: HashSet<String> = pop.iter().map(|x| x.genome_key()).collect();
+    keys.len() as f64 / pop.len() as f64
+}
@@ -0,0 +1,140 @@
+//! When a run should end.
+
+use crate::individual::Individual;
+use crate::stats;
+use std::fmt;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+// Population diversity is costly to measure, so it is only checked this often
+const DIVERSITY_INTERVAL: usize = 100;
+
+/// Set while a run is in progress.
+pub static RUNNING: AtomicBool = AtomicBool::new(false);
+/// Set when Ctrl-C asks the current run to stop.
+pub static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Ctrl-C handler.  A run finishes its current generation before stopping; outside of a run there
+/// is nothing to save, so the process exits straight away.
+pub fn interrupt() {
+    if RUNNING.load(Ordering::SeqCst) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+    } else {
+        process::exit(130);
+    }
+}
+
+/// Mark a run as started and return whether another was already running, for [`finish`].  A run
+/// inside another keeps the outer run's Ctrl-C, so one press stops both.
+pub fn start() -> bool {
+    let outer = RUNNING.swap(true, Ordering::SeqCst);
+    if !outer {
+        INTERRUPTED.store(false, Ordering::SeqCst);
+    }
+    outer
+}
+
+/// Mark a run as ended, unless it ran inside another.  Only the outermost run clears Ctrl-C, so
+/// runs inside it never swallow a press meant for it.
+pub fn finish(outer: bool) {
+    RUNNING.store(outer, Ordering::SeqCst);
+    if !outer {
+        INTERRUPTED.store(false, Ordering::SeqCst);
+    }
+}
+
+/// True when Ctrl-C asked the current run to stop.
+pub fn interrupted() -> bool {
+    RUNNING.load(Ordering::SeqCst) && INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Conditions that end a run early.  The generation count is always a hard limit; every other
+/// condition is optional and the first one met wins.
+#[derive(Debug, Clone, Default)]
+pub struct StoppingCriteria {
+    pub generations: usize,
+    pub target_fitness: Option<i32>,
+    pub stall_generations: Option<usize>,
+    pub time_budget: Option<Duration>,
+    pub min_diversity: Option<f64>,
+}
+
+/// Why a run ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+    Completed,
+    TargetReached(i32),
+    Stalled(usize),
+    OutOfTime(Duration),
+    Converged(f64),
+    Interrupted,
+    /// The run could not go on, for the reason given
+    Failed(String),
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StopReason::Completed => write!(f, "ran every requested generation"),
+            StopReason::TargetReached(fitness) => {
+                write!(f, "reached target fitness with best fitness {}", fitness)
+            }
+            StopReason::Stalled(gens) => {
+                write!(f, "best fitness unchanged for {} generations", gens)
+            }
+            StopReason::OutOfTime(elapsed) => {
+                write!(f, "time budget spent after {:.1}s", elapsed.as_secs_f64())
+            }
+            StopReason::Converged(diversity) => {
+                write!(
+                    f,
+                    "population converged to {:.4} distinct genomes",
+                    diversity
+                )
+            }
+            StopReason::Interrupted => write!(f, "interrupted"),
+            StopReason::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl StoppingCriteria {
+    /// Called after each generation with the number of generations run so far, how many of those
+    /// passed since the best fitness last improved, and the time spent in this run.
+    pub fn check(
+        &self,
+        generation: usize,
+        stalled: usize,
+        elapsed: Duration,
+        pop: &[Individual],
+    ) -> Option<StopReason> {
+        if interrupted() {
+            return Some(StopReason::Interrupted);
+        }
+
+        let best = pop.iter().map(|x| x.fitness).min().unwrap();
+        if self.target_fitness.is_some_and(|target| best <= target) {
+            return Some(StopReason::TargetReached(best));
+        }
+        if self.stall_generations.is_some_and(|gens| stalled >= gens) {
+            return Some(StopReason::Stalled(stalled));
+        }
+        if self.time_budget.is_some_and(|budget| elapsed >= budget) {
+            return Some(StopReason::OutOfTime(elapsed));
+        }
+        if let Some(min_diversity) = self.min_diversity {
+            if generation.is_multiple_of(DIVERSITY_INTERVAL) {
+                let diversity = stats::distinct_genomes(pop);
+                if diversity < min_diversity {
+                    return Some(StopReason::Converged(diversity));
+                }
+            }
+        }
+        if generation >= self.generations {
+            return Some(StopReason::Completed);
+        }
+
+        None
+    }
+}
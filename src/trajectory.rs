@@ -0,0 +1,218 @@
+//! Single-trajectory solvers: simulated annealing and tabu search.
+//!
+//! Instead of evolving a whole grid, these follow one individual through the same legal moves
+//! [`Individual::moves`] that mutation makes, scoring each with [`Individual::score`].  They start
+//! from the population's best individual and hand their best find back to it when the run ends.
+
+use crate::individual::Individual;
+use crate::population::Betrayal;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::VecDeque;
+
+/// Solvers accepted by [`parse_solver`].
+pub const SOLVERS: &str = "ga, anneal[:geometric|linear|log[:START:END]], tabu[:TENURE]";
+
+/// How [`Population::run`](crate::population::Population::run) searches.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Solver {
+    /// The cellular genetic algorithm
+    Genetic,
+    Annealing(Annealing),
+    Tabu(Tabu),
+}
+
+impl Solver {
+    /// Configuration text that [`parse_solver`] turns back into this solver.
+    pub fn describe(&self) -> String {
+        match self {
+            Solver::Genetic => "ga".to_string(),
+            Solver::Annealing(annealing) => format!(
+                "anneal:{}:{}:{}",
+                annealing.cooling.describe(),
+                annealing.start,
+                annealing.end
+            ),
+            Solver::Tabu(tabu) => format!("tabu:{}", tabu.tenure),
+        }
+    }
+}
+
+/// Build a solver from configuration text such as `anneal:linear:20:0.5` or `tabu:50`.
+pub fn parse_solver(text: &str) -> Result<Solver, String> {
+    let mut parts = text.split(':');
+    match parts.next().unwrap() {
+        "ga" => Ok(Solver::Genetic),
+        "anneal" => {
+            let mut annealing = Annealing::default();
+            if let Some(cooling) = parts.next() {
+                annealing.cooling = match cooling {
+                    "geometric" => Cooling::Geometric,
+                    "linear" => Cooling::Linear,
+                    "log" => Cooling::Logarithmic,
+                    _ => return Err(format!("Unknown cooling schedule {}", cooling)),
+                };
+            }
+            if let (Some(start), Some(end)) = (parts.next(), parts.next()) {
+                annealing.start = start
+                    .parse()
+                    .map_err(|_| format!("Bad start temperature: {}", start))?;
+                annealing.end = end
+                    .parse()
+                    .map_err(|_| format!("Bad end temperature: {}", end))?;
+            }
+            if !(annealing.start >= annealing.end && annealing.end > 0.0) {
+                return Err("Temperatures must satisfy START >= END > 0".to_string());
+            }
+            Ok(Solver::Annealing(annealing))
+        }
+        "tabu" => {
+            let tenure = parts.next().unwrap_or("20");
+            match tenure.parse() {
+                Ok(tenure) => Ok(Solver::Tabu(Tabu { tenure })),
+                Err(_) => Err(format!("Bad tabu tenure: {}", tenure)),
+            }
+        }
+        name => Err(format!("Unknown solver {}, expected {}", name, SOLVERS)),
+    }
+}
+
+/// Where a single-trajectory search is: the individual it is on and the best it has seen.
+pub struct Trajectory {
+    pub current: Individual,
+    pub best: Individual,
+    /// Genome keys of recently visited individuals, for tabu search
+    recent: VecDeque<String>,
+}
+
+impl Trajectory {
+    pub fn new(start: Individual) -> Trajectory {
+        Trajectory {
+            best: start.clone(),
+            current: start,
+            recent: VecDeque::new(),
+        }
+    }
+
+    fn moved(&mut self, ind: Individual) {
+        if ind.fitness < self.best.fitness {
+            self.best = ind.clone();
+        }
+        self.current = ind;
+    }
+}
+
+/// How the annealing temperature falls from `start` to `end` over a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cooling {
+    /// Multiply by the same factor every step
+    Geometric,
+    /// Subtract the same amount every step
+    Linear,
+    /// Fall with the logarithm of the step, never below `end`
+    Logarithmic,
+}
+
+impl Cooling {
+    fn describe(&self) -> &'static str {
+        match self {
+            Cooling::Geometric => "geometric",
+            Cooling::Linear => "linear",
+            Cooling::Logarithmic => "log",
+        }
+    }
+}
+
+/// Simulated annealing: try one random move per step, always taking improvements and taking a
+/// move that worsens fitness by `d` with probability `exp(-d / temperature)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annealing {
+    pub cooling: Cooling,
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Default for Annealing {
+    fn default() -> Annealing {
+        Annealing {
+            cooling: Cooling::Geometric,
+            start: 10.0,
+            end: 0.1,
+        }
+    }
+}
+
+impl Annealing {
+    /// Temperature at `step` of a run of `steps`.
+    pub fn temperature(&self, step: usize, steps: usize) -> f64 {
+        let progress = step as f64 / steps.max(1) as f64;
+        match self.cooling {
+            Cooling::Geometric => self.start * (self.end / self.start).powf(progress),
+            Cooling::Linear => self.start + (self.end - self.start) * progress,
+            Cooling::Logarithmic => (self.start / (1.0 + (1.0 + step as f64).ln())).max(self.end),
+        }
+    }
+
+    pub fn step(
+        &self,
+        trajectory: &mut Trajectory,
+        step: usize,
+        steps: usize,
+        betrayal: &Betrayal,
+        rng: &mut StdRng,
+    ) {
+        // Pick a character first so only its moves need building
+        let names = trajectory.current.names();
+        let name = names.choose(rng).unwrap();
+        let mut candidate = match trajectory
+            .current
+            .character_moves(name, &betrayal.locks)
+            .choose(rng)
+        {
+            Some(candidate) => candidate.clone(),
+            None => return,
+        };
+        candidate.score(&betrayal.rankings);
+
+        let delta = (candidate.fitness - trajectory.current.fitness) as f64;
+        let temperature = self.temperature(step, steps);
+        if delta <= 0.0 || rng.gen::<f64>() < (-delta / temperature).exp() {
+            trajectory.moved(candidate);
+        }
+    }
+}
+
+/// Tabu search: take the best move every step, even a worsening one, but never back to one of
+/// the last `tenure` individuals visited unless it beats the best seen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tabu {
+    pub tenure: usize,
+}
+
+impl Tabu {
+    pub fn step(&self, trajectory: &mut Trajectory, betrayal: &Betrayal) {
+        let mut chosen: Option<Individual> = None;
+        for mut candidate in trajectory.current.moves(&betrayal.locks) {
+            candidate.score(&betrayal.rankings);
+            if chosen
+                .as_ref()
+                .is_some_and(|x| x.fitness <= candidate.fitness)
+            {
+                continue;
+            }
+            let aspiration = candidate.fitness < trajectory.best.fitness;
+            if aspiration || !trajectory.recent.contains(&candidate.genome_key()) {
+                chosen = Some(candidate);
+            }
+        }
+
+        if let Some(ind) = chosen {
+            trajectory.recent.push_back(trajectory.current.genome_key());
+            while trajectory.recent.len() > self.tenure {
+                trajectory.recent.pop_front();
+            }
+            trajectory.moved(ind);
+        }
+    }
+}